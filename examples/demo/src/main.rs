@@ -6,7 +6,7 @@
     holding buffers for the duration of a data transfer."
 )]
 
-use defmt::{debug, info};
+use defmt::{debug, info, warn};
 use embassy_executor::Spawner;
 use embassy_futures::select::{Either, select};
 use embassy_sync::{
@@ -45,10 +45,10 @@ use tildagon::{
     },
     front::{
         FrontBoardLeds,
-        leds::{BaseBoardLed, FrontLeds, HexpansionPortLed},
+        leds::{BaseBoardLed, FrontLeds, HexpansionPortIndicator},
     },
     hexpansions::{HexpansionPort, HexpansionPortControl, HexpansionPortEvent, HexpansionState},
-    i2c::{SharedI2cBus, SharedI2cDevice, SystemI2cBus},
+    i2c::{FrontBoardI2cBus, SharedI2cBus, SharedI2cDevice, SystemI2cBus},
     led_power::OnboardLedPower,
     pins::PinControl,
     resources::*,
@@ -87,6 +87,9 @@ async fn main(spawner: Spawner) {
     static I2C_SYSTEM: StaticCell<SharedI2cBus<tildagon::i2c::SystemI2cBus>> = StaticCell::new();
     let i2c_system = I2C_SYSTEM.init(tildagon::i2c::system_i2c_bus(i2c_bus));
 
+    static I2C_FRONT: StaticCell<SharedI2cBus<FrontBoardI2cBus>> = StaticCell::new();
+    let i2c_front = I2C_FRONT.init(tildagon::i2c::front_i2c_bus(i2c_bus));
+
     let mut pin_control = PinControl::new(i2c_system).await.unwrap();
     let pins = pin_control.pins();
 
@@ -95,9 +98,10 @@ async fn main(spawner: Spawner) {
 
     let mut buttons = tildagon::front::emf2024::SystemButtonCollection::new(pins.buttons);
 
-    let mut hex_slots = HexpansionPortControl::new(pins.hexpansion_detect)
-        .await
-        .unwrap();
+    let mut hex_slots =
+        HexpansionPortControl::new(SharedI2cDevice::new(i2c_system), pins.hexpansion_detect)
+            .await
+            .unwrap();
 
     let rmt: Rmt<'_, esp_hal::Blocking> = Rmt::new(p.RMT, Rate::from_mhz(80)).unwrap();
 
@@ -117,7 +121,12 @@ async fn main(spawner: Spawner) {
             static EXECUTOR: StaticCell<Executor> = StaticCell::new();
             let executor = EXECUTOR.init(Executor::new());
             executor.run(|spawner| {
-                spawner.must_spawn(display_task(r.front_board, p.SPI2, p.DMA_CH0));
+                spawner.must_spawn(display_task(
+                    r.front_board,
+                    SharedI2cDevice::new(i2c_front),
+                    p.SPI2,
+                    p.DMA_CH0,
+                ));
             });
         },
     );
@@ -147,14 +156,22 @@ async fn main(spawner: Spawner) {
                     event_pub.publish(Event::Button(event)).await;
                 }
 
-                for event in hex_slots.update(&regs) {
-                    info!("Hexpansion event: {}", event);
-                    event_pub.publish(Event::HexpansionPort(event)).await;
+                match hex_slots.update(&regs) {
+                    Ok(events) => {
+                        for event in events {
+                            info!("Hexpansion event: {}", event);
+                            event_pub.publish(Event::HexpansionPort(event)).await;
+                        }
+                    }
+                    Err(e) => warn!("Failed to update hexpansion ports: {}", e),
                 }
             }
             Either::Second(WaitResult::Lagged(_)) => panic!(),
             Either::Second(WaitResult::Message(msg)) => {
-                hex_slots.set_enabled(msg.slot, msg.enable).await.unwrap();
+                // A failed port is reported as faulted by the next update, and can be retried
+                if let Err(e) = hex_slots.set_enabled(msg.slot, msg.enable).await {
+                    warn!("Failed to set hexpansion port {} enabled: {}", msg.slot, e);
+                }
             }
         }
     }
@@ -246,16 +263,27 @@ use tildagon::front::FrontBoardDisplay;
 #[embassy_executor::task]
 async fn display_task(
     front_board: FrontBoardResources<'static>,
+    mut i2c_front: SharedI2cDevice<FrontBoardI2cBus>,
     spi: SPI2<'static>,
     dma: DMA_CH0<'static>,
 ) {
     let mut display_buffer = [0_u8; 512];
     let mut display = <tildagon::front::Emf2024FrontBoard as FrontBoardDisplay>::Display::init(
         front_board,
+        &mut i2c_front,
         spi,
         dma,
+        {
+            let (rx_buffer, rx_descriptors, _, _) =
+                esp_hal::dma_buffers!(tildagon::front::emf2024::GC9A01_RX_DMA_BUFFER_SIZE, 0);
+            esp_hal::dma::DmaRxBuf::new(rx_descriptors, rx_buffer).unwrap()
+        },
+        esp_hal::dma_tx_buffer!(32000).unwrap(),
         &mut display_buffer,
-    );
+        Default::default(),
+    )
+    .await
+    .unwrap();
     display.clear(Rgb565::BLACK).unwrap();
 
     let mut event_sub = EVENT_CHANNEL.subscriber().unwrap();
@@ -272,6 +300,14 @@ async fn display_task(
         .font(&FONT_10X20)
         .text_color(Rgb565::YELLOW)
         .build();
+    let character_style_blue = MonoTextStyleBuilder::new()
+        .font(&FONT_10X20)
+        .text_color(Rgb565::BLUE)
+        .build();
+    let character_style_magenta = MonoTextStyleBuilder::new()
+        .font(&FONT_10X20)
+        .text_color(Rgb565::MAGENTA)
+        .build();
 
     let textbox_style = TextBoxStyleBuilder::new()
         .alignment(HorizontalAlignment::Center)
@@ -338,9 +374,12 @@ async fn display_task(
                     text,
                     Rectangle::with_center(centre + Point::new(x, 60), Size::new(width, 50)),
                     match event.state() {
+                        // Same colours as the port LEDs
                         HexpansionState::Disabled => character_style_red,
                         HexpansionState::Empty => character_style_orange,
+                        HexpansionState::Inserting => character_style_blue,
                         HexpansionState::Occupied => character_style,
+                        HexpansionState::Fault => character_style_magenta,
                     },
                     textbox_style,
                 )
@@ -364,9 +403,7 @@ async fn led_task(
 
     let mut leds = <tildagon::front::Emf2024FrontBoard as FrontBoardLeds>::PixelBuffer::default();
 
-    const HEX_DISABLED_COLOUR: RGB8 = RGB8::new(255, 0, 0);
-    const HEX_EMPTY_COLOUR: RGB8 = RGB8::new(255, 192, 0);
-    const HEX_OCCUPIED_COLOUR: RGB8 = RGB8::new(255, 255, 255);
+    let mut hex_indicator = HexpansionPortIndicator::new(Default::default());
 
     *leds.base_board() = RGB8::new(128, 0, 128);
 
@@ -385,11 +422,8 @@ async fn led_task(
         match select(event_sub.next_message(), front_pixel_tick.next()).await {
             Either::First(WaitResult::Lagged(_)) => panic!(),
             Either::First(WaitResult::Message(Event::HexpansionPort(event))) => {
-                *leds.hexpansion_port(*event.port()) = match *event.state() {
-                    HexpansionState::Disabled => HEX_DISABLED_COLOUR,
-                    HexpansionState::Empty => HEX_EMPTY_COLOUR,
-                    HexpansionState::Occupied => HEX_OCCUPIED_COLOUR,
-                };
+                hex_indicator.handle_port_event(&event);
+                hex_indicator.render(&mut leds);
 
                 adapter.write(leds.into_iter()).unwrap();
             }
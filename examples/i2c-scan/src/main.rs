@@ -15,9 +15,7 @@ use panic_rtt_target as _;
 use static_cell::StaticCell;
 use tildagon::{
     esp_hal::{self, clock::CpuClock, timer::timg::TimerGroup},
-    hexpansions::{
-        HexpansionEepromHeader, HexpansionManifestVersion, HexpansionPort, HexpansionPortControl,
-    },
+    hexpansions::{HexpansionEepromHeader, HexpansionManifestVersion, HexpansionPortControl},
     i2c::{
         FrontBoardI2cBus, HexpansionAI2cBus, HexpansionBI2cBus, HexpansionCI2cBus,
         HexpansionDI2cBus, HexpansionEI2cBus, HexpansionFI2cBus, SharedI2cBus, SharedI2cDevice,
@@ -85,34 +83,12 @@ async fn main(_spawner: Spawner) {
     let mut usb_sw = UsbSwitch::new(pins.usb);
     usb_sw.set(UsbPort::In).await.unwrap();
 
-    let mut hex_slots = HexpansionPortControl::new(pins.hexpansion_detect)
-        .await
-        .unwrap();
-
-    hex_slots
-        .set_enabled(HexpansionPort::A, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::B, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::C, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::D, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::E, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::F, true)
-        .await
-        .unwrap();
+    let mut hex_slots =
+        HexpansionPortControl::new(SharedI2cDevice::new(i2c_system), pins.hexpansion_detect)
+            .await
+            .unwrap();
+
+    hex_slots.enable_all().await.unwrap();
 
     {
         let mut top_eeprom = tildagon::eeprom::detect_eeprom(SharedI2cDevice::new(i2c_front))
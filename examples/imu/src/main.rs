@@ -10,9 +10,10 @@ use core::fmt::Write;
 use embassy_executor::Spawner;
 use embassy_sync::{
     blocking_mutex::raw::CriticalSectionRawMutex,
+    channel::Channel,
     pubsub::{PubSubChannel, WaitResult},
 };
-use embassy_time::{Duration, Ticker, Timer};
+use embassy_time::{Duration, Instant, Timer};
 use embedded_graphics::{
     Drawable,
     draw_target::DrawTarget,
@@ -31,7 +32,6 @@ use heapless::String;
 use panic_rtt_target as _;
 use static_cell::StaticCell;
 use tildagon::{
-    bmi2::types::{Data, PwrCtrl},
     esp_hal::{
         self,
         clock::CpuClock,
@@ -41,9 +41,11 @@ use tildagon::{
         timer::timg::TimerGroup,
     },
     front::FrontBoardDisplay,
-    i2c::{SharedI2cBus, SharedI2cDevice},
+    i2c::{FrontBoardI2cBus, SharedI2cBus, SharedI2cDevice},
+    imu::{ImuCalibrationStore, ImuSample, ImuService, ImuServiceConfig},
     pins::PinControl,
     resources::*,
+    system_interrupt::SystemInterrupt,
     usb::{UsbPort, UsbSwitch},
 };
 
@@ -75,22 +77,27 @@ async fn main(_spawner: Spawner) {
     static I2C_SYSTEM: StaticCell<SharedI2cBus<tildagon::i2c::SystemI2cBus>> = StaticCell::new();
     let i2c_system = I2C_SYSTEM.init(tildagon::i2c::system_i2c_bus(i2c_bus));
 
+    static I2C_FRONT: StaticCell<SharedI2cBus<FrontBoardI2cBus>> = StaticCell::new();
+    let i2c_front = I2C_FRONT.init(tildagon::i2c::front_i2c_bus(i2c_bus));
+
     let mut pin_control = PinControl::new(i2c_system).await.unwrap();
     let pins = pin_control.pins();
 
     let mut usb_sw = UsbSwitch::new(pins.usb);
     usb_sw.set(UsbPort::In).await.unwrap();
 
-    let mut imu = tildagon::imu::init(SharedI2cDevice::new(i2c_system))
-        .await
-        .unwrap();
-    imu.set_pwr_ctrl(PwrCtrl {
-        aux_en: false,
-        gyr_en: true,
-        acc_en: true,
-        temp_en: false,
-    })
+    let imu_config = ImuServiceConfig::default();
+    let mut calibration = ImuCalibrationStore::in_flash(p.FLASH).unwrap();
+    let imu = tildagon::imu::init(
+        SharedI2cDevice::new(i2c_system),
+        &imu_config.sensors,
+        &mut calibration,
+    )
+    .await
     .unwrap();
+    let mut imu = ImuService::new(imu, pins.imu, imu_config).await.unwrap();
+
+    let mut system_interrupt = SystemInterrupt::new(r.system);
 
     static APP_CORE_STACK: StaticCell<Stack<8192>> = StaticCell::new();
     let app_core_stack = APP_CORE_STACK.init(Stack::new());
@@ -105,7 +112,12 @@ async fn main(_spawner: Spawner) {
             static EXECUTOR: StaticCell<Executor> = StaticCell::new();
             let executor = EXECUTOR.init(Executor::new());
             executor.run(|spawner| {
-                spawner.must_spawn(display_task(r.front_board, p.SPI2, p.DMA_CH0));
+                spawner.must_spawn(display_task(
+                    r.front_board,
+                    SharedI2cDevice::new(i2c_front),
+                    p.SPI2,
+                    p.DMA_CH0,
+                ));
             });
         },
     );
@@ -115,26 +127,62 @@ async fn main(_spawner: Spawner) {
     // Use channels to indicate readiness properly, mkay.
     Timer::after_millis(500).await;
 
-    let mut imu_tick = Ticker::every(Duration::from_millis(250));
+    static IMU_SAMPLES: Channel<CriticalSectionRawMutex, ImuSample, 32> = Channel::new();
+    let imu_sender = IMU_SAMPLES.sender();
+
     let event_pub = EVENT_CHANNEL.publisher().unwrap();
+    let mut last_published = Instant::MIN;
 
     loop {
-        imu_tick.next().await;
+        system_interrupt.wait_for_interrupt().await;
+
+        let regs = pin_control.read_system_bus_input_registers().await.unwrap();
+        imu.update(&regs, &imu_sender).await.unwrap();
+
+        // Only the most recent sample is displayed, at a readable rate
+        let Some(sample) = core::iter::from_fn(|| IMU_SAMPLES.try_receive().ok())
+            .inspect(capture)
+            .last()
+        else {
+            continue;
+        };
+        if sample.time().saturating_duration_since(last_published) < Duration::from_millis(250) {
+            continue;
+        }
+        last_published = *sample.time();
 
-        let data: Data = imu.get_data().unwrap();
+        let data = sample.data();
         event_pub
             .publish(Event::ImuAxisData(ImuData {
-                gyro_x: data.gyr.x,
-                gyro_y: data.gyr.y,
-                gyro_z: data.gyr.z,
-                accel_x: data.acc.x,
-                accel_y: data.acc.y,
-                accel_z: data.acc.z,
+                gyro_x: data.gyr[0],
+                gyro_y: data.gyr[1],
+                gyro_z: data.gyr[2],
+                accel_x: data.acc[0],
+                accel_y: data.acc[1],
+                accel_z: data.acc[2],
             }))
             .await;
     }
 }
 
+/// Log a sample as a line of a trace for the fusion crate's tests, see `fusion/src/traces.rs`.
+#[cfg(feature = "capture")]
+fn capture(sample: &ImuSample) {
+    let data = sample.data();
+    defmt::println!(
+        "[{}, {}, {}, {}, {}, {}],",
+        data.acc[0],
+        data.acc[1],
+        data.acc[2],
+        data.gyr[0],
+        data.gyr[1],
+        data.gyr[2]
+    );
+}
+
+#[cfg(not(feature = "capture"))]
+fn capture(_sample: &ImuSample) {}
+
 #[derive(Clone)]
 enum Event {
     ImuAxisData(ImuData),
@@ -156,16 +204,27 @@ static EVENT_CHANNEL: PubSubChannel<CriticalSectionRawMutex, Event, 12, 4, 4> =
 #[embassy_executor::task]
 async fn display_task(
     front_board: FrontBoardResources<'static>,
+    mut i2c_front: SharedI2cDevice<FrontBoardI2cBus>,
     spi: SPI2<'static>,
     dma: DMA_CH0<'static>,
 ) {
     let mut display_buffer = [0_u8; 512];
     let mut display = <tildagon::front::Emf2024FrontBoard as FrontBoardDisplay>::Display::init(
         front_board,
+        &mut i2c_front,
         spi,
         dma,
+        {
+            let (rx_buffer, rx_descriptors, _, _) =
+                esp_hal::dma_buffers!(tildagon::front::emf2024::GC9A01_RX_DMA_BUFFER_SIZE, 0);
+            esp_hal::dma::DmaRxBuf::new(rx_descriptors, rx_buffer).unwrap()
+        },
+        esp_hal::dma_tx_buffer!(32000).unwrap(),
         &mut display_buffer,
-    );
+        Default::default(),
+    )
+    .await
+    .unwrap();
     display.clear(Rgb565::BLACK).unwrap();
 
     let character_style = MonoTextStyleBuilder::new()
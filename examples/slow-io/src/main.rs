@@ -6,18 +6,21 @@
     holding buffers for the duration of a data transfer."
 )]
 
+use defmt::info;
 use embassy_executor::Spawner;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_time::Timer;
-use embedded_hal::pwm::SetDutyCycle;
 use panic_rtt_target as _;
 use static_cell::StaticCell;
 use tildagon::{
-    embedded_aw9523::PinConfiguration,
     esp_hal::{self, clock::CpuClock, timer::timg::TimerGroup},
-    hexpansions::{HexpansionPort, HexpansionPortControl},
-    i2c::SharedI2cBus,
+    hexpansions::{
+        HexpansionPortControl, LowSpeedIo, LsInterruptDispatcher, LsInterrupts, LsPin, LsWaiter,
+    },
+    i2c::{SharedI2cBus, SharedI2cDevice, SystemI2cBus},
     pins::PinControl,
     resources::*,
+    system_interrupt::SystemInterrupt,
     usb::{UsbPort, UsbSwitch},
 };
 
@@ -28,7 +31,7 @@ extern crate alloc;
 esp_bootloader_esp_idf::esp_app_desc!();
 
 #[esp_rtos::main]
-async fn main(_spawner: Spawner) {
+async fn main(spawner: Spawner) {
     rtt_target::rtt_init_defmt!();
 
     let config = tildagon::esp_hal::Config::default().with_cpu_clock(CpuClock::max());
@@ -51,65 +54,71 @@ async fn main(_spawner: Spawner) {
 
     let mut pin_control = PinControl::new(i2c_system).await.unwrap();
     let pins = pin_control.pins();
+    let dispatcher = LsInterruptDispatcher::new(&pins);
 
     let mut usb_sw = UsbSwitch::new(pins.usb);
     usb_sw.set(UsbPort::In).await.unwrap();
 
-    let mut hex_slots = HexpansionPortControl::new(pins.hexpansion_detect)
-        .await
-        .unwrap();
+    let mut hex_slots =
+        HexpansionPortControl::new(SharedI2cDevice::new(i2c_system), pins.hexpansion_detect)
+            .await
+            .unwrap();
 
     // A little time for other tasks to start.
     // Hacky as all fuck but good enough for a demo.
     // Use channels to indicate readiness properly, mkay.
     Timer::after_millis(500).await;
 
-    hex_slots
-        .set_enabled(HexpansionPort::A, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::B, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::C, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::D, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::E, true)
-        .await
-        .unwrap();
-    hex_slots
-        .set_enabled(HexpansionPort::F, true)
-        .await
-        .unwrap();
-
-    let hex_a_pins = pins.hexpansion_a;
-
-    let mut a1 = hex_a_pins.ls_1.try_into_led().await.unwrap();
-    let mut a2 = hex_a_pins.ls_2.try_into_led().await.unwrap();
-    let mut a3 = hex_a_pins.ls_3.try_into_led().await.unwrap();
-    let mut a4 = hex_a_pins.ls_4.try_into_led().await.unwrap();
-    let mut a5 = hex_a_pins.ls_5.try_into_led().await.unwrap();
+    hex_slots.enable_all().await.unwrap();
 
+    let mut hex_a = LowSpeedIo::new(SharedI2cDevice::new(i2c_system), pins.hexpansion_a);
+
+    for pin in LEDS {
+        hex_a.into_led(pin).await.unwrap();
+    }
+
+    // LS5 is left as an input, changes of whatever drives it are reported
+    let waiter = hex_a.waiter(LsPin::Ls5, &LS_INTERRUPTS).await.unwrap();
+
+    spawner.must_spawn(fade_task(hex_a));
+    spawner.must_spawn(input_task(waiter));
+
+    let mut system_interrupt = SystemInterrupt::new(r.system);
+
+    loop {
+        system_interrupt.wait_for_interrupt().await;
+
+        let regs = pin_control.read_system_bus_input_registers().await.unwrap();
+        dispatcher.dispatch(&regs, &LS_INTERRUPTS).unwrap();
+    }
+}
+
+static LS_INTERRUPTS: LsInterrupts<CriticalSectionRawMutex> = LsInterrupts::new();
+
+const LEDS: [LsPin; 4] = [LsPin::Ls1, LsPin::Ls2, LsPin::Ls3, LsPin::Ls4];
+
+#[embassy_executor::task]
+async fn fade_task(mut hex_a: LowSpeedIo<SharedI2cDevice<SystemI2cBus>>) {
     loop {
-        ramp(&mut a1).await;
-        ramp(&mut a2).await;
-        ramp(&mut a3).await;
-        ramp(&mut a4).await;
-        ramp(&mut a5).await;
+        for pin in LEDS {
+            for brightness in 0..=255u8 {
+                hex_a.set_brightness(pin, brightness).await.unwrap();
+                Timer::after_millis(4).await;
+            }
+            hex_a.set_brightness(pin, 0).await.unwrap();
+        }
     }
 }
 
-async fn ramp<T: SetDutyCycle>(io: &mut T) {
-    for pct in 0..100 {
-        io.set_duty_cycle(pct).unwrap();
-        Timer::after_millis(10).await;
+#[embassy_executor::task]
+async fn input_task(waiter: LsWaiter<'static, CriticalSectionRawMutex>) {
+    loop {
+        let high = waiter.wait_for_any_edge().await;
+        info!(
+            "Hexpansion {} {} is now {}",
+            waiter.port(),
+            waiter.pin(),
+            high
+        );
     }
-    io.set_duty_cycle(0).unwrap();
 }
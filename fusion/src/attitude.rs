@@ -0,0 +1,163 @@
+//! Attitude estimation by fusing accelerometer and gyroscope samples with a complementary filter.
+//!
+//! Angles are in degrees, in the axes of the IMU. Roll and pitch are corrected by gravity, yaw is integrated from the
+//! gyroscope alone and so will drift.
+
+use micromath::F32Ext;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Attitude {
+    pub roll: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+/// Roll and pitch implied by the direction of gravity, from an acceleration in g.
+pub fn attitude_from_accel(acc: [f32; 3]) -> (f32, f32) {
+    let [ax, ay, az] = acc;
+    let roll = F32Ext::atan2(ay, az).to_degrees();
+    let pitch = F32Ext::atan2(-ax, F32Ext::sqrt(ay * ay + az * az)).to_degrees();
+    (roll, pitch)
+}
+
+/// Advance an attitude by one sample.
+///
+/// `acc` is in g and `gyr` in degrees per second (with any bias already removed), `dt` is the time since the previous
+/// sample in seconds. Without a previous attitude the roll and pitch are taken from the accelerometer and yaw starts at
+/// zero.
+///
+/// The gyroscope is integrated and then pulled towards the accelerometer by `1 - gyro_weight`, but only when the
+/// magnitude of the acceleration is within `accel_tolerance_g` of 1 g, i.e. when the IMU is not otherwise accelerating.
+pub fn complementary_update(
+    previous: Option<Attitude>,
+    acc: [f32; 3],
+    gyr: [f32; 3],
+    dt: f32,
+    gyro_weight: f32,
+    accel_tolerance_g: f32,
+) -> Attitude {
+    let (accel_roll, accel_pitch) = attitude_from_accel(acc);
+
+    let Some(previous) = previous else {
+        return Attitude {
+            roll: accel_roll,
+            pitch: accel_pitch,
+            yaw: 0.0,
+        };
+    };
+
+    let [ax, ay, az] = acc;
+    let magnitude = F32Ext::sqrt(ax * ax + ay * ay + az * az);
+    let weight = match F32Ext::abs(magnitude - 1.0) <= accel_tolerance_g {
+        true => 1.0 - gyro_weight,
+        false => 0.0,
+    };
+
+    let roll = previous.roll + gyr[0] * dt;
+    let pitch = previous.pitch + gyr[1] * dt;
+    let yaw = previous.yaw + gyr[2] * dt;
+
+    Attitude {
+        roll: wrap(roll + wrap(accel_roll - roll) * weight),
+        pitch: wrap(pitch + wrap(accel_pitch - pitch) * weight),
+        yaw: wrap(yaw),
+    }
+}
+
+/// Wrap an angle to -180 to 180 degrees.
+pub fn wrap(angle: f32) -> f32 {
+    let angle = (angle + 180.0) % 360.0;
+    if angle < 0.0 {
+        angle + 180.0
+    } else {
+        angle - 180.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        StationaryDetector,
+        traces::{FLAT, LSB_PER_DPS, LSB_PER_G, ROLL_90, SAMPLE_PERIOD, YAW_DRIFT},
+    };
+
+    const GYRO_WEIGHT: f32 = 0.98;
+    const ACCEL_TOLERANCE_G: f32 = 0.2;
+
+    /// Run a trace through the filter as the badge does, returning the final attitude.
+    fn run(trace: &[[i16; 6]], gyro_bias: [i16; 3]) -> Attitude {
+        trace
+            .iter()
+            .fold(None, |previous, sample| {
+                let acc = [0, 1, 2].map(|i| sample[i] as f32 / LSB_PER_G);
+                let gyr = [0, 1, 2].map(|i| (sample[i + 3] - gyro_bias[i]) as f32 / LSB_PER_DPS);
+                let dt = match previous {
+                    Some(_) => SAMPLE_PERIOD,
+                    None => 0.0,
+                };
+                Some(complementary_update(
+                    previous,
+                    acc,
+                    gyr,
+                    dt,
+                    GYRO_WEIGHT,
+                    ACCEL_TOLERANCE_G,
+                ))
+            })
+            .unwrap()
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn flat() {
+        let attitude = run(&FLAT, [0; 3]);
+        assert_near(attitude.roll, 0.0, 1.0);
+        assert_near(attitude.pitch, 0.0, 1.0);
+        assert_near(attitude.yaw, 0.0, 1.0);
+    }
+
+    #[test]
+    fn roll_90() {
+        let attitude = run(&ROLL_90, [0; 3]);
+        assert_near(attitude.roll, 90.0, 1.0);
+        assert_near(attitude.pitch, 0.0, 1.0);
+        assert_near(attitude.yaw, 0.0, 1.0);
+    }
+
+    #[test]
+    fn yaw_drifts_with_gyro_bias() {
+        let attitude = run(&YAW_DRIFT, [0; 3]);
+        assert_near(attitude.roll, 0.0, 1.0);
+        assert_near(attitude.pitch, 0.0, 1.0);
+
+        // The trace has a Z bias of 8 LSB, about 0.49 degrees per second
+        let seconds = (YAW_DRIFT.len() - 1) as f32 * SAMPLE_PERIOD;
+        assert_near(attitude.yaw, 8.0 / LSB_PER_DPS * seconds, 0.3);
+    }
+
+    #[test]
+    fn yaw_holds_with_measured_bias() {
+        let mut detector = StationaryDetector::new((5.0 * LSB_PER_DPS) as i32);
+        for sample in &YAW_DRIFT[..100] {
+            detector.push([sample[3], sample[4], sample[5]]).unwrap();
+        }
+
+        let attitude = run(&YAW_DRIFT, detector.mean().unwrap());
+        assert_near(attitude.yaw, 0.0, 0.3);
+    }
+
+    #[test]
+    fn wraps() {
+        assert_near(wrap(190.0), -170.0, 1e-3);
+        assert_near(wrap(-190.0), 170.0, 1e-3);
+        assert_near(wrap(45.0), 45.0, 1e-3);
+    }
+}
@@ -0,0 +1,17 @@
+//! Hardware independent IMU sensor fusion (and tap detection) used by the [`tildagon`](https://crates.io/crates/tildagon) crate.
+//!
+//! This is separate from the board support crate so that it builds on the host, where it is tested against traces
+//! of the BMI270 with `cargo test` from this directory.
+
+#![cfg_attr(not(test), no_std)]
+
+mod attitude;
+mod stationary;
+mod tap;
+
+pub use attitude::*;
+pub use stationary::*;
+pub use tap::*;
+
+#[cfg(test)]
+mod traces;
@@ -0,0 +1,81 @@
+//! Detection of the IMU being held still, while averaging the gyroscope to measure its bias.
+
+/// A reading was too far from the first to be stationary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Moved;
+
+pub struct StationaryDetector {
+    max_deviation: i32,
+    reference: Option<[i16; 3]>,
+    sum: [i32; 3],
+    count: i32,
+}
+
+impl StationaryDetector {
+    /// Any raw gyroscope reading more than `max_deviation` from the first on any axis is considered movement.
+    pub fn new(max_deviation: i32) -> Self {
+        Self {
+            max_deviation,
+            reference: None,
+            sum: [0; 3],
+            count: 0,
+        }
+    }
+
+    /// Add a raw gyroscope reading.
+    pub fn push(&mut self, gyr: [i16; 3]) -> Result<(), Moved> {
+        let reference = *self.reference.get_or_insert(gyr);
+        if (0..3).any(|i| (gyr[i] as i32 - reference[i] as i32).abs() > self.max_deviation) {
+            return Err(Moved);
+        }
+
+        for (s, g) in self.sum.iter_mut().zip(gyr) {
+            *s += g as i32;
+        }
+        self.count += 1;
+
+        Ok(())
+    }
+
+    /// Number of readings added.
+    pub fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Average of the readings added, i.e. the gyroscope bias if the IMU was still.
+    pub fn mean(&self) -> Option<[i16; 3]> {
+        (self.count > 0).then(|| self.sum.map(|s| (s / self.count) as i16))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traces::{FLAT, ROLL_90};
+
+    #[test]
+    fn still() {
+        let mut detector = StationaryDetector::new(80);
+        for sample in &FLAT {
+            detector.push([sample[3], sample[4], sample[5]]).unwrap();
+        }
+
+        assert_eq!(detector.count(), FLAT.len());
+        assert!(detector.mean().unwrap().iter().all(|g| g.abs() <= 2));
+    }
+
+    #[test]
+    fn moved() {
+        let mut detector = StationaryDetector::new(80);
+        detector
+            .push([ROLL_90[0][3], ROLL_90[0][4], ROLL_90[0][5]])
+            .unwrap();
+        assert_eq!(detector.push([200, 0, 0]), Err(Moved));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(StationaryDetector::new(80).mean(), None);
+    }
+}
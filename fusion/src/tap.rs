@@ -0,0 +1,208 @@
+//! Detection of taps on the badge from accelerometer readings.
+//!
+//! A tap is a short spike in acceleration: the change between consecutive readings jumps above a threshold, then settles
+//! again within a few readings. A longer disturbance is the badge being handled rather than tapped, so is ignored. Two
+//! taps in quick succession are a double tap.
+//!
+//! Everything is counted in readings, so the timings scale with the output data rate.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Tap {
+    Single,
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TapConfig {
+    /// Change between consecutive raw accelerometer readings (on any axis) that counts as a tap
+    pub threshold: i32,
+
+    /// Most readings a tap may stay above the threshold for
+    pub max_duration: u16,
+
+    /// Readings after a tap during which the accelerometer is left to settle, so ringing is not seen as another tap
+    pub quiet: u16,
+
+    /// Most readings after the end of a tap that a second tap may start in to make a double tap
+    pub double_tap_window: u16,
+}
+
+pub struct TapDetector {
+    config: TapConfig,
+    previous: Option<[i16; 3]>,
+
+    /// Readings the current tap has been above the threshold for
+    active: Option<u16>,
+
+    /// Readings left to ignore after a tap
+    quiet: u16,
+
+    /// Readings since the first of a possible double tap ended
+    pending: Option<u16>,
+}
+
+impl TapDetector {
+    pub fn new(config: TapConfig) -> Self {
+        Self {
+            config,
+            previous: None,
+            active: None,
+            quiet: 0,
+            pending: None,
+        }
+    }
+
+    pub fn config(&self) -> &TapConfig {
+        &self.config
+    }
+
+    /// Add a raw accelerometer reading, returning a tap once it is recognised.
+    ///
+    /// A single tap is only returned once the double tap window has passed without a second, so is delayed by up to
+    /// [`TapConfig::double_tap_window`] readings.
+    pub fn push(&mut self, acc: [i16; 3]) -> Option<Tap> {
+        let previous = self.previous.replace(acc).unwrap_or(acc);
+        let change = (0..3)
+            .map(|i| (acc[i] as i32 - previous[i] as i32).abs())
+            .max()
+            .unwrap_or(0);
+        let above = change > self.config.threshold;
+
+        let mut tap = None;
+
+        if let Some(pending) = &mut self.pending {
+            *pending += 1;
+            if *pending > self.config.double_tap_window && self.active.is_none() {
+                self.pending = None;
+                tap = Some(Tap::Single);
+            }
+        }
+
+        if let Some(active) = &mut self.active {
+            if above {
+                *active += 1;
+                return tap;
+            }
+
+            let duration = *active;
+            self.active = None;
+
+            if duration > self.config.max_duration {
+                // Handled rather than tapped, which also breaks up a double tap
+                self.pending = None;
+                return tap;
+            }
+
+            self.quiet = self.config.quiet;
+            return match self.pending.take() {
+                Some(_) => Some(Tap::Double),
+                None => {
+                    self.pending = Some(0);
+                    tap
+                }
+            };
+        }
+
+        if self.quiet > 0 {
+            self.quiet -= 1;
+            return tap;
+        }
+
+        if above {
+            self.active = Some(1);
+        }
+
+        tap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traces::{FLAT, ROLL_90};
+
+    const CONFIG: TapConfig = TapConfig {
+        threshold: 1500,
+        max_duration: 3,
+        quiet: 3,
+        double_tap_window: 30,
+    };
+
+    fn accel(sample: &[i16; 6]) -> [i16; 3] {
+        [sample[0], sample[1], sample[2]]
+    }
+
+    /// The flat trace with a one reading spike on Z at each of `at`
+    fn tapped(at: &[usize]) -> Vec<[i16; 3]> {
+        FLAT.iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                let mut acc = accel(sample);
+                if at.contains(&i) {
+                    acc[2] += 4000;
+                }
+                acc
+            })
+            .collect()
+    }
+
+    fn run(trace: impl IntoIterator<Item = [i16; 3]>) -> Vec<(usize, Tap)> {
+        let mut detector = TapDetector::new(CONFIG);
+        trace
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, acc)| detector.push(acc).map(|tap| (i, tap)))
+            .collect()
+    }
+
+    #[test]
+    fn still() {
+        assert_eq!(run(FLAT.iter().map(accel)), []);
+    }
+
+    #[test]
+    fn single() {
+        let taps = run(tapped(&[50]));
+        assert_eq!(taps.len(), 1);
+
+        // Reported once the double tap window has passed
+        let (at, tap) = taps[0];
+        assert_eq!(tap, Tap::Single);
+        assert!(at > 50 + CONFIG.double_tap_window as usize);
+    }
+
+    #[test]
+    fn double() {
+        assert_eq!(run(tapped(&[50, 65])), [(67, Tap::Double)]);
+    }
+
+    #[test]
+    fn far_apart() {
+        let taps = run(tapped(&[20, 120]));
+        assert_eq!(taps.len(), 2);
+        assert!(taps.iter().all(|(_, tap)| *tap == Tap::Single));
+    }
+
+    #[test]
+    fn ringing_is_not_a_second_tap() {
+        // Spike, then bouncing back the other way straight after the tap ends
+        let mut trace = tapped(&[50]);
+        trace[53][2] -= 3000;
+        assert_eq!(run(trace).len(), 1);
+    }
+
+    #[test]
+    fn handling_is_not_a_tap() {
+        // Turning the badge over changes the readings by more than the threshold for a long time
+        let mut trace: Vec<_> = FLAT[..20].iter().map(accel).collect();
+        for (i, sample) in ROLL_90[..20].iter().enumerate() {
+            let mut acc = accel(sample);
+            acc[1] += if i % 2 == 0 { 3000 } else { -3000 };
+            trace.push(acc);
+        }
+        trace.extend(FLAT[..60].iter().map(accel));
+        assert_eq!(run(trace), []);
+    }
+}
@@ -0,0 +1,926 @@
+//! BMI270 traces for the tests, as raw readings with the accelerometer at ±4 g and the gyroscope at ±2000 dps.
+//!
+//! Each sample is accelerometer X, Y, Z then gyroscope X, Y, Z, in data register order. The traces are synthesised from
+//! the nominal sensitivities with a few LSB of pseudo-random noise, rather than captured from a badge.
+//!
+//! Captures from a badge are taken with the `imu` example built with its `capture` feature, which logs every sample
+//! (at this rate and these ranges, the defaults of `ImuServiceConfig`) as a line of one of these arrays. Captures are
+//! added alongside the synthetic traces rather than replacing them, as the synthetic ones have exactly known motion.
+
+/// Time between samples, in seconds (100 Hz)
+pub(crate) const SAMPLE_PERIOD: f32 = 0.01;
+
+pub(crate) const LSB_PER_G: f32 = 8192.0;
+pub(crate) const LSB_PER_DPS: f32 = 16.384;
+
+/// Lying still, face up
+pub(crate) const FLAT: [[i16; 6]; 200] = [
+    [6, -8, 8199, -2, 0, 0],
+    [4, -5, 8182, -1, 2, -2],
+    [-8, 6, 8204, 3, 2, 0],
+    [2, 5, 8182, 1, 1, 3],
+    [-7, -2, 8200, 1, -2, -3],
+    [11, -18, 8196, -2, 0, 0],
+    [-4, -13, 8198, 1, -1, 3],
+    [13, -4, 8200, -2, 1, 0],
+    [8, 16, 8188, 2, 1, -3],
+    [-5, -16, 8194, -1, -2, 2],
+    [-17, -17, 8180, 2, 0, 0],
+    [-5, -16, 8190, -1, -1, -2],
+    [18, 12, 8189, 0, -3, 3],
+    [-14, -6, 8202, 1, 2, -2],
+    [8, -18, 8212, -3, -2, 3],
+    [7, -18, 8182, 1, -2, 2],
+    [-2, 14, 8192, -2, 2, -3],
+    [12, -8, 8177, -2, 0, -2],
+    [10, 9, 8183, -2, 1, 1],
+    [15, -11, 8177, 0, 3, 0],
+    [-5, -18, 8183, 1, -2, -1],
+    [-3, -5, 8204, 2, 1, 0],
+    [3, 5, 8202, 2, -1, -1],
+    [-18, -3, 8190, -2, 1, 3],
+    [12, 18, 8209, 3, 1, 0],
+    [5, 19, 8174, -3, -1, 1],
+    [6, -15, 8196, 1, 1, 2],
+    [13, -9, 8176, 1, 3, 1],
+    [16, -20, 8202, 1, 2, -1],
+    [12, -10, 8173, 2, 1, 2],
+    [-16, -18, 8173, -1, 1, -3],
+    [-2, 16, 8182, -3, -2, 2],
+    [17, 13, 8191, -1, 2, -2],
+    [14, 19, 8187, 1, -1, 2],
+    [7, -3, 8176, 2, 2, -1],
+    [-11, 12, 8188, 1, -2, 3],
+    [-15, -16, 8201, -1, 2, 0],
+    [-8, -14, 8201, -1, -1, 1],
+    [-19, 15, 8208, 1, -2, 2],
+    [-12, -9, 8206, 2, 1, 1],
+    [-4, 6, 8210, -1, 0, 2],
+    [10, 1, 8179, 1, 0, 3],
+    [-5, 18, 8182, 2, -3, 3],
+    [-2, -18, 8189, -2, -1, -2],
+    [-16, -15, 8200, -3, -1, -1],
+    [12, -20, 8186, -1, 1, -2],
+    [8, 2, 8193, 0, -3, -1],
+    [1, 0, 8205, -1, 3, -2],
+    [-9, 10, 8183, 1, 1, -1],
+    [-15, 16, 8203, 0, 2, 0],
+    [-17, 12, 8178, 1, -3, -1],
+    [-5, 13, 8173, 2, 3, 0],
+    [-4, 14, 8204, 3, -1, -1],
+    [-11, -17, 8196, 1, 0, 2],
+    [-1, -11, 8182, 0, -1, -2],
+    [7, -10, 8186, -2, 0, 0],
+    [-10, -12, 8196, -3, 2, 2],
+    [-8, -20, 8203, -2, 2, -3],
+    [-20, -1, 8201, 1, 1, -3],
+    [-1, 16, 8199, 1, 0, 0],
+    [-6, 1, 8211, 2, -1, 3],
+    [5, 4, 8193, 1, -2, -2],
+    [1, -12, 8204, -1, -2, 1],
+    [-13, 5, 8181, -3, -3, -2],
+    [10, -1, 8189, -2, -1, 1],
+    [-7, -18, 8182, -2, 0, 2],
+    [-1, 2, 8202, 2, 3, 0],
+    [20, 10, 8195, 1, 0, 2],
+    [-16, 9, 8176, -1, -1, 1],
+    [-5, -4, 8201, 2, -2, 2],
+    [-11, 8, 8184, 2, 2, 2],
+    [10, 6, 8175, 2, -2, 1],
+    [-15, -20, 8203, -2, 0, 3],
+    [-15, -15, 8204, -2, 3, 3],
+    [15, -10, 8173, 0, -3, 2],
+    [20, -12, 8201, -2, 1, 3],
+    [6, 11, 8176, 2, 1, 0],
+    [-14, 0, 8186, 0, -2, -1],
+    [-13, -4, 8192, -1, -3, 1],
+    [4, -18, 8187, 2, -3, -1],
+    [8, 18, 8182, -2, 3, -3],
+    [0, 15, 8202, 3, 0, 1],
+    [-20, -16, 8192, 2, 0, -1],
+    [3, 18, 8179, -1, -2, 2],
+    [-12, -2, 8175, 0, 0, 2],
+    [-10, 11, 8189, -1, 2, -1],
+    [-10, -17, 8206, 2, 2, -3],
+    [-1, 10, 8199, 2, -2, -3],
+    [18, 6, 8183, 3, -1, -1],
+    [-2, -18, 8182, 2, 2, 2],
+    [18, 3, 8189, 2, 0, 3],
+    [17, -6, 8172, 0, 0, -1],
+    [-19, 6, 8189, -2, 0, 0],
+    [2, 4, 8203, -2, -2, -1],
+    [15, -10, 8198, -3, 1, 0],
+    [11, 2, 8199, 3, -2, 0],
+    [3, -3, 8172, 1, 1, 3],
+    [-17, -11, 8186, 2, -1, 0],
+    [-3, 6, 8187, 2, 1, -2],
+    [-1, 13, 8195, -3, 2, -2],
+    [12, 17, 8212, -2, 1, 2],
+    [9, -5, 8181, 3, 3, 3],
+    [-19, 12, 8176, 3, -2, 0],
+    [11, 11, 8188, 1, 2, 2],
+    [7, -20, 8200, -1, 1, -3],
+    [14, -4, 8182, 0, 3, 1],
+    [10, 0, 8193, -1, 0, 1],
+    [-19, 8, 8194, -3, 3, 1],
+    [5, -12, 8179, 0, 0, 0],
+    [-7, -10, 8204, 1, 2, -2],
+    [-3, 3, 8195, -2, -1, -3],
+    [4, 14, 8182, -2, 1, -1],
+    [15, -6, 8200, 0, -2, 1],
+    [10, 2, 8182, -1, 1, 2],
+    [-18, -18, 8199, -2, -1, 0],
+    [15, 8, 8177, 0, 0, -1],
+    [-15, -9, 8173, 1, -1, 1],
+    [19, 11, 8200, 0, 3, 0],
+    [5, -17, 8195, 1, -1, 2],
+    [-2, 18, 8178, 2, 2, 2],
+    [-3, -15, 8195, -2, -2, 0],
+    [-10, -7, 8173, 1, 2, 0],
+    [-5, 9, 8186, 1, 1, -2],
+    [16, 4, 8173, 1, -1, 3],
+    [7, 5, 8188, 3, -1, 1],
+    [-5, -5, 8194, 0, 2, -1],
+    [-11, 18, 8211, 3, -2, -2],
+    [9, 5, 8201, -1, -1, 0],
+    [-9, -4, 8182, 1, -1, -1],
+    [4, 6, 8200, -3, 0, -2],
+    [12, 17, 8174, -3, 3, -1],
+    [12, 15, 8202, -3, 2, -1],
+    [-4, 14, 8177, -1, 3, 2],
+    [3, -16, 8191, 2, 2, 1],
+    [12, 11, 8176, 1, 0, -1],
+    [-15, -3, 8187, 2, -2, 3],
+    [-6, -10, 8179, 0, 0, -1],
+    [15, -6, 8208, -2, 0, -1],
+    [3, 10, 8180, 3, 2, 2],
+    [-19, -8, 8197, -1, -2, -2],
+    [-1, -3, 8172, -2, 1, -2],
+    [17, -11, 8181, -1, 2, 0],
+    [-9, 3, 8207, -2, 1, -2],
+    [9, 2, 8187, 1, 0, 1],
+    [0, -3, 8187, -1, -2, -1],
+    [-16, 13, 8174, 2, -2, -3],
+    [-11, 3, 8192, 2, 0, 1],
+    [-15, 5, 8210, 2, 0, 0],
+    [-18, -11, 8176, 0, -2, 1],
+    [-5, 15, 8184, 0, 2, 2],
+    [13, 1, 8209, 2, 3, -2],
+    [-11, -5, 8176, -2, 2, -3],
+    [9, 17, 8198, -2, 0, -1],
+    [-6, 3, 8183, 2, -2, -1],
+    [7, 8, 8203, -3, 1, 0],
+    [8, -16, 8179, 0, 1, 2],
+    [0, -11, 8201, 3, -2, -2],
+    [0, 5, 8177, -1, -1, -3],
+    [-14, 0, 8200, 3, -3, -1],
+    [18, -11, 8173, -1, -1, 1],
+    [-7, 3, 8198, -2, 2, 0],
+    [19, 15, 8202, 3, 2, 2],
+    [-4, -19, 8186, 1, -1, -3],
+    [3, -12, 8208, 3, 2, 1],
+    [0, -14, 8198, -1, -1, 3],
+    [6, -15, 8175, 2, 1, 1],
+    [2, 8, 8196, 1, -1, 2],
+    [-17, 16, 8211, 0, 2, -3],
+    [18, 13, 8172, -1, -2, 0],
+    [4, -1, 8198, 3, 2, -2],
+    [2, 1, 8174, -3, 3, 2],
+    [-19, -12, 8177, 0, 3, -2],
+    [-11, 19, 8178, -2, -2, 0],
+    [-19, 12, 8199, -1, 1, 1],
+    [-18, -5, 8177, -2, 2, -1],
+    [6, -8, 8177, 3, 2, 1],
+    [9, -14, 8191, -1, 0, 2],
+    [-6, -17, 8186, 0, -1, -2],
+    [-18, -18, 8185, 1, -2, 1],
+    [9, 13, 8183, 2, -3, 0],
+    [-7, 6, 8182, -1, -1, 1],
+    [17, -5, 8200, 1, -3, 1],
+    [0, -7, 8211, -3, -3, 2],
+    [13, -17, 8182, 1, 1, 1],
+    [-3, 15, 8176, 1, -1, -1],
+    [17, -18, 8196, 2, -1, 2],
+    [9, 12, 8188, 0, 2, -1],
+    [-19, 13, 8190, -1, 2, 2],
+    [-10, 9, 8201, 0, -2, 1],
+    [10, 1, 8185, 1, 1, -3],
+    [10, 5, 8181, 1, 3, 2],
+    [16, -9, 8201, -1, 3, 0],
+    [6, 7, 8179, -1, 0, -3],
+    [8, -16, 8192, 3, -2, -2],
+    [-2, -16, 8188, -3, -2, -1],
+    [19, 7, 8188, 2, -2, -3],
+    [-12, 17, 8183, -1, 3, 3],
+    [-14, 2, 8181, 3, 0, 0],
+    [10, -12, 8186, 0, 3, 0],
+    [-1, 18, 8202, 2, -2, 2],
+];
+
+/// Lying still, rolled 90 degrees onto its side (+Y up)
+pub(crate) const ROLL_90: [[i16; 6]; 200] = [
+    [10, 8182, 4, -2, -3, 2],
+    [18, 8202, 12, -1, -2, -1],
+    [-9, 8207, 0, 2, -1, -2],
+    [0, 8177, 10, 0, -1, 1],
+    [-8, 8205, 8, 0, 2, -2],
+    [-10, 8210, 8, -3, 1, 0],
+    [5, 8189, -3, -1, 2, 3],
+    [4, 8201, -3, 0, -1, 1],
+    [-12, 8209, 13, -1, 3, 3],
+    [13, 8178, 17, -2, 3, 0],
+    [1, 8188, 20, 2, -3, -1],
+    [2, 8208, -14, 3, 0, 0],
+    [13, 8190, 17, 2, -1, -2],
+    [4, 8176, 12, 2, 0, 1],
+    [17, 8204, -10, 0, 1, 1],
+    [14, 8194, 15, -1, 0, -2],
+    [2, 8184, -18, 0, 1, -1],
+    [5, 8192, -17, -2, 2, -1],
+    [17, 8205, -3, -1, 0, 2],
+    [-14, 8182, -9, 0, 1, -2],
+    [-13, 8206, -12, -2, 2, 2],
+    [-2, 8203, -2, -2, -1, -1],
+    [4, 8196, -1, -2, -1, 3],
+    [0, 8178, -2, -1, 1, 1],
+    [5, 8207, -5, 2, 2, 1],
+    [13, 8207, -2, -2, -1, -2],
+    [10, 8206, 12, -3, 1, -1],
+    [3, 8197, -18, -3, -1, 1],
+    [-16, 8205, -12, 3, -3, -2],
+    [13, 8208, 5, 0, -1, -1],
+    [-8, 8187, 6, 2, -2, 2],
+    [5, 8206, 7, 2, -3, 1],
+    [3, 8198, 14, -1, 0, -2],
+    [-9, 8199, -9, -2, 2, 1],
+    [18, 8187, 3, -1, 2, -2],
+    [-1, 8184, 10, 0, 1, 0],
+    [10, 8193, -13, 3, -3, -1],
+    [6, 8176, -6, 3, -2, -1],
+    [-9, 8201, -2, 0, 0, -1],
+    [-11, 8173, 5, 2, -2, 2],
+    [-14, 8201, 5, 1, -2, 0],
+    [-10, 8188, 19, 3, -2, 1],
+    [-12, 8175, 15, 3, 1, -2],
+    [19, 8209, -14, 0, 1, 0],
+    [16, 8212, -19, 1, 0, -1],
+    [14, 8186, -19, 3, 3, -2],
+    [9, 8205, 11, 2, -1, 0],
+    [-7, 8205, 11, 3, 2, 1],
+    [7, 8174, -13, 1, 1, 2],
+    [4, 8179, -6, 3, 3, -1],
+    [-12, 8173, 5, -3, 0, -1],
+    [14, 8185, 4, -1, -1, 3],
+    [-20, 8207, -1, 0, -1, -1],
+    [-1, 8194, -8, 1, 2, 2],
+    [12, 8176, 13, 1, -2, 1],
+    [-12, 8203, -6, -2, -1, 1],
+    [-12, 8207, -11, 2, -2, 2],
+    [16, 8186, 2, -3, 3, -1],
+    [3, 8189, 5, -2, 3, -1],
+    [2, 8208, -13, 1, 1, -2],
+    [-8, 8172, -15, -1, 1, -2],
+    [16, 8182, -4, 1, -1, -1],
+    [7, 8205, -9, -2, -1, -1],
+    [-7, 8187, -7, 3, 3, 3],
+    [9, 8194, -16, -2, 2, 1],
+    [2, 8183, -5, 3, 0, 2],
+    [-14, 8173, 7, 1, 3, -1],
+    [-2, 8188, 12, 0, -2, 0],
+    [-19, 8175, 7, -1, 0, 2],
+    [15, 8179, -12, 2, 1, 3],
+    [15, 8202, -5, 2, 0, 2],
+    [9, 8204, 5, 0, -2, 0],
+    [-11, 8212, 6, 1, -3, 0],
+    [18, 8202, -9, 3, -1, -2],
+    [-17, 8210, 12, -2, 1, 0],
+    [15, 8193, -5, -1, -2, -1],
+    [-3, 8180, -19, 2, -2, 1],
+    [6, 8209, -8, 2, -2, 0],
+    [-13, 8202, -17, -3, -3, 1],
+    [14, 8189, -13, 2, 2, -2],
+    [12, 8188, 1, -1, 1, -3],
+    [0, 8203, 0, 1, -1, -2],
+    [16, 8182, -13, 0, 1, 1],
+    [-7, 8192, 17, -1, 1, 0],
+    [19, 8194, -7, -3, 3, 1],
+    [-14, 8193, -14, -2, 2, -2],
+    [15, 8188, -11, -1, 1, -2],
+    [17, 8193, -18, 1, 1, 3],
+    [12, 8208, -11, -3, -2, 0],
+    [17, 8178, -7, 1, -2, 0],
+    [6, 8191, -1, 0, 1, 3],
+    [-1, 8204, 5, -1, 1, -3],
+    [-1, 8175, -12, -2, 3, 1],
+    [-18, 8177, -9, 1, -3, 1],
+    [14, 8198, 1, -2, 2, 0],
+    [17, 8205, 7, -3, 0, -3],
+    [20, 8175, -10, 2, -1, 2],
+    [-14, 8212, 20, -1, -3, 1],
+    [-17, 8187, 14, 1, 0, -2],
+    [-4, 8198, -16, 1, -2, 1],
+    [-15, 8211, 10, -2, 0, 1],
+    [19, 8196, -9, -2, 0, 3],
+    [9, 8191, 13, -2, 3, 3],
+    [11, 8182, 13, -2, 0, -3],
+    [4, 8199, -14, 0, -3, 1],
+    [7, 8193, -19, 3, -3, 1],
+    [7, 8191, -16, -2, -1, 2],
+    [5, 8201, -4, 1, 1, 2],
+    [10, 8189, 14, -1, 2, -1],
+    [-1, 8177, -18, 2, 1, -1],
+    [18, 8188, -12, 2, -2, 0],
+    [-2, 8188, 14, -1, -2, 3],
+    [0, 8181, 12, 1, -2, -1],
+    [1, 8196, -13, 3, -2, -1],
+    [17, 8202, 12, -1, -3, 0],
+    [-5, 8203, -14, 0, -2, 0],
+    [13, 8179, -15, -1, 2, 2],
+    [-7, 8207, 13, -1, -2, -1],
+    [18, 8179, -18, 1, 2, 1],
+    [-15, 8184, 10, -1, 0, -1],
+    [-6, 8196, -4, 1, -3, 1],
+    [-3, 8187, 10, -1, 0, 0],
+    [-9, 8179, -2, -2, 2, 1],
+    [1, 8180, 9, 1, 1, 1],
+    [7, 8179, -15, 0, -2, -3],
+    [2, 8197, -5, 1, -3, 1],
+    [-9, 8191, -14, 0, -1, -2],
+    [-12, 8210, -9, 0, -1, 1],
+    [7, 8202, 6, 3, 0, -2],
+    [20, 8206, 0, 3, -2, 0],
+    [-7, 8190, 0, 1, -3, 2],
+    [14, 8203, -5, -2, -3, 1],
+    [1, 8181, -1, 1, 2, 0],
+    [2, 8185, -15, 1, 3, 1],
+    [7, 8188, 20, 2, 0, -2],
+    [4, 8211, -14, -3, 3, 2],
+    [18, 8180, 15, -2, -2, 2],
+    [10, 8197, 19, -2, 1, -1],
+    [-19, 8194, 6, -2, 0, -2],
+    [-15, 8178, -9, -2, 2, -1],
+    [9, 8194, 18, 0, 2, 1],
+    [-8, 8204, -13, -1, 0, -3],
+    [-10, 8178, 8, -1, 2, -1],
+    [10, 8182, -16, 1, 2, -1],
+    [14, 8204, -16, 0, 2, 0],
+    [-8, 8187, -1, 2, -2, 2],
+    [8, 8185, 10, 1, 0, 1],
+    [-11, 8195, 10, 1, -2, -1],
+    [11, 8190, 6, 2, -1, -2],
+    [-6, 8185, 4, 2, -2, -2],
+    [4, 8201, -12, 0, 2, -1],
+    [9, 8193, -19, -2, -1, -2],
+    [-8, 8180, -10, 1, 1, -1],
+    [-1, 8198, -9, -1, 3, -1],
+    [-12, 8181, -14, -2, 3, -2],
+    [-9, 8189, -8, 0, -2, 1],
+    [1, 8210, -15, -2, -1, 2],
+    [-17, 8183, -3, 2, 2, 0],
+    [16, 8187, -1, 2, -1, -2],
+    [0, 8174, -16, -1, -3, 1],
+    [2, 8199, -11, 1, 0, -1],
+    [-1, 8186, 9, 0, -1, -2],
+    [-18, 8206, 6, 0, 0, -1],
+    [11, 8212, 13, -3, -3, 0],
+    [-11, 8186, -4, -2, 1, 0],
+    [10, 8202, 12, 0, 0, -3],
+    [-18, 8191, 1, -1, -1, 0],
+    [15, 8179, 18, -2, 1, -3],
+    [-2, 8194, -17, -1, 1, 3],
+    [16, 8187, -6, 0, -2, -2],
+    [4, 8195, 10, 3, 0, -3],
+    [8, 8208, 5, 3, -1, -3],
+    [10, 8211, -10, 0, 1, 2],
+    [4, 8180, -14, 2, -2, -2],
+    [12, 8207, 2, -3, -1, -2],
+    [10, 8173, 6, 2, 0, -2],
+    [-5, 8210, -19, -2, -2, -2],
+    [19, 8186, 20, 3, 0, 1],
+    [-17, 8182, 8, -2, 2, 0],
+    [5, 8207, 8, 2, -1, -2],
+    [4, 8199, -10, -3, 1, 1],
+    [-17, 8212, -8, 2, 2, -1],
+    [16, 8200, -15, 1, 1, -3],
+    [-12, 8175, -18, 3, -2, -3],
+    [1, 8188, 9, 2, 3, -3],
+    [4, 8177, 18, 3, -2, -2],
+    [8, 8201, 6, -2, -3, 3],
+    [16, 8185, 8, 0, 0, 0],
+    [-14, 8193, 14, -2, 1, 1],
+    [15, 8178, 2, 0, 2, -1],
+    [-14, 8211, -9, 0, 3, 1],
+    [4, 8206, 8, 3, -3, -2],
+    [18, 8189, -8, -1, 2, 0],
+    [1, 8190, -17, 0, 0, -2],
+    [17, 8194, 10, 0, -3, 0],
+    [9, 8212, -15, -1, -1, 3],
+    [8, 8187, 4, 2, 2, 0],
+    [-16, 8206, 14, 2, 0, -1],
+    [6, 8179, 3, 3, 2, 1],
+    [11, 8190, -15, 1, -1, -2],
+];
+
+/// Lying still, face up, with a gyroscope Z bias of 8 LSB
+pub(crate) const YAW_DRIFT: [[i16; 6]; 500] = [
+    [10, -14, 8191, 1, 1, 10],
+    [-1, 7, 8209, -3, 2, 7],
+    [-1, 0, 8184, 1, -2, 9],
+    [-9, 1, 8193, 2, 2, 7],
+    [5, 4, 8180, -2, 0, 7],
+    [-7, -14, 8207, 1, 1, 6],
+    [11, 15, 8185, -1, 2, 9],
+    [-18, -12, 8204, 1, -2, 5],
+    [-16, -18, 8201, 1, 2, 7],
+    [-11, -10, 8207, 0, 3, 10],
+    [-2, -6, 8209, 1, -1, 6],
+    [14, -15, 8189, 0, -1, 5],
+    [0, 4, 8199, 1, -1, 9],
+    [14, 4, 8180, 2, -1, 5],
+    [-3, -4, 8201, 2, 2, 6],
+    [-2, -5, 8191, 1, -1, 8],
+    [-1, -4, 8188, -1, 2, 8],
+    [12, -5, 8181, -1, -1, 8],
+    [2, -4, 8195, 2, 2, 10],
+    [-20, -11, 8201, -1, 3, 8],
+    [-14, 1, 8192, -2, -2, 5],
+    [7, 11, 8190, 1, 1, 8],
+    [-18, -5, 8204, 1, -2, 10],
+    [14, 8, 8176, -2, -1, 6],
+    [7, -18, 8204, 0, 0, 8],
+    [1, 8, 8187, 1, -2, 7],
+    [-17, -15, 8202, -1, 2, 6],
+    [6, -20, 8193, -1, -1, 5],
+    [18, 11, 8197, -3, 1, 8],
+    [9, -19, 8184, 1, -2, 8],
+    [14, 1, 8204, 0, -1, 10],
+    [20, 19, 8175, -2, -1, 6],
+    [-18, 18, 8188, 2, 1, 7],
+    [-12, 16, 8208, 1, -2, 8],
+    [-15, 9, 8197, 2, 1, 8],
+    [-12, -14, 8192, -2, 3, 7],
+    [11, 11, 8201, -2, 3, 6],
+    [19, 19, 8202, 0, 0, 7],
+    [17, -18, 8175, 0, 2, 9],
+    [8, 15, 8184, 2, 0, 10],
+    [5, 14, 8206, -2, 1, 7],
+    [-12, -7, 8199, -3, 0, 11],
+    [10, 17, 8176, 2, -1, 6],
+    [5, -7, 8209, 3, 0, 11],
+    [-12, 0, 8184, -3, -2, 5],
+    [0, -7, 8177, -3, 1, 9],
+    [-10, 0, 8187, 1, 2, 10],
+    [18, -7, 8189, 1, 3, 7],
+    [-18, 15, 8181, 3, -1, 11],
+    [5, 1, 8175, 3, 2, 8],
+    [-20, -4, 8172, 1, -2, 11],
+    [9, 18, 8200, 3, -2, 8],
+    [6, -16, 8203, 0, -2, 9],
+    [7, -10, 8184, 0, 2, 5],
+    [19, -13, 8207, -1, -2, 7],
+    [-18, -8, 8203, 0, 3, 7],
+    [20, -15, 8175, -2, -1, 8],
+    [-4, -18, 8212, 0, -2, 6],
+    [-15, 4, 8178, 0, -1, 9],
+    [-16, 10, 8200, 2, -2, 7],
+    [-2, 3, 8194, 2, 2, 6],
+    [-10, -12, 8202, -3, -1, 9],
+    [-2, 18, 8186, -1, -1, 9],
+    [9, -3, 8199, -1, -2, 9],
+    [-18, -4, 8193, 2, 0, 8],
+    [-4, 17, 8172, 0, 3, 6],
+    [3, -14, 8195, 1, -3, 9],
+    [-6, -7, 8200, 0, -1, 7],
+    [-19, -4, 8183, -3, -1, 11],
+    [0, -1, 8201, 1, 1, 7],
+    [-4, 10, 8183, 2, -2, 9],
+    [-9, 0, 8190, 1, 0, 10],
+    [18, 9, 8191, 1, 1, 7],
+    [3, -7, 8195, 1, -3, 6],
+    [-6, 8, 8191, 1, 0, 5],
+    [-11, 16, 8196, 1, -1, 7],
+    [19, -16, 8209, 2, 3, 7],
+    [-5, 13, 8186, 2, -1, 7],
+    [19, -10, 8212, -3, -1, 6],
+    [4, -8, 8194, -3, 0, 9],
+    [9, -19, 8201, 1, -1, 8],
+    [-15, -11, 8173, 2, 1, 7],
+    [14, -5, 8193, 2, 1, 6],
+    [7, 15, 8192, 2, 0, 9],
+    [-10, 15, 8206, 0, 0, 9],
+    [4, -16, 8203, 1, 1, 6],
+    [17, -1, 8202, 0, 0, 8],
+    [1, 19, 8204, 2, 3, 10],
+    [9, 10, 8211, 3, -2, 6],
+    [2, -20, 8204, -3, 3, 7],
+    [-11, 1, 8209, 1, -1, 8],
+    [-18, -3, 8207, -1, -1, 6],
+    [-12, -8, 8209, 2, 1, 9],
+    [9, 13, 8181, -2, 2, 10],
+    [12, -2, 8208, -3, -3, 6],
+    [7, 16, 8186, 2, -1, 10],
+    [14, -2, 8178, 2, 1, 9],
+    [13, -17, 8193, 0, 1, 10],
+    [-5, -11, 8191, 3, -1, 9],
+    [-14, -13, 8199, -2, 2, 8],
+    [10, 15, 8204, 0, 3, 9],
+    [-5, 18, 8181, 0, 1, 8],
+    [18, 12, 8173, -3, 0, 10],
+    [13, -14, 8210, 2, 2, 6],
+    [6, -1, 8183, -1, 2, 10],
+    [0, -1, 8177, -2, 1, 6],
+    [-15, -19, 8202, 1, -3, 9],
+    [-4, 14, 8189, 2, 0, 11],
+    [2, 7, 8206, -1, 0, 7],
+    [-6, -7, 8200, 0, 2, 10],
+    [6, -5, 8172, 1, -3, 5],
+    [15, -12, 8212, 0, 2, 7],
+    [-5, 3, 8208, 1, 3, 7],
+    [13, 3, 8173, 0, 0, 8],
+    [-7, 1, 8182, -1, -1, 5],
+    [-20, 12, 8188, 3, -2, 9],
+    [-18, -20, 8188, 0, 2, 5],
+    [7, -4, 8173, 1, 2, 7],
+    [-5, 15, 8181, 1, 1, 11],
+    [-18, -1, 8198, 0, 1, 7],
+    [-14, -1, 8210, 3, 1, 10],
+    [16, 3, 8177, -1, 0, 9],
+    [-19, 18, 8193, -2, -1, 5],
+    [20, 10, 8201, 0, 2, 11],
+    [6, 18, 8206, -2, 2, 10],
+    [10, 14, 8182, 2, -1, 6],
+    [9, 1, 8196, 2, 3, 6],
+    [-20, -16, 8211, 1, -2, 11],
+    [-12, 2, 8212, -2, -2, 7],
+    [12, -10, 8177, 2, 2, 6],
+    [0, 14, 8179, 2, -1, 9],
+    [-4, -9, 8192, 2, 2, 7],
+    [12, -17, 8201, -1, -1, 11],
+    [3, -12, 8179, 1, 0, 7],
+    [10, 3, 8204, -2, 1, 8],
+    [17, -6, 8177, -1, 1, 6],
+    [11, -2, 8186, -3, 1, 9],
+    [5, 2, 8191, 1, -3, 5],
+    [-1, 7, 8178, -3, -1, 9],
+    [-4, 4, 8207, 3, 1, 6],
+    [-14, -20, 8208, -1, 0, 8],
+    [10, -1, 8190, -1, 3, 11],
+    [15, -16, 8185, -2, 0, 5],
+    [8, -2, 8188, 2, -1, 10],
+    [-3, 8, 8182, 0, 2, 7],
+    [10, -13, 8195, 2, 0, 6],
+    [-11, -1, 8203, 0, 2, 6],
+    [-7, -16, 8183, 2, 2, 11],
+    [8, -16, 8199, -1, 2, 11],
+    [4, 7, 8202, -1, 1, 8],
+    [-11, 0, 8195, 0, -2, 6],
+    [20, -16, 8186, 1, -3, 5],
+    [-16, -5, 8204, 1, 2, 6],
+    [14, -11, 8208, 0, -1, 9],
+    [7, -4, 8209, 2, -2, 10],
+    [18, 17, 8192, 2, -1, 11],
+    [13, -19, 8206, -2, 1, 9],
+    [-2, 7, 8208, -1, -2, 10],
+    [2, -16, 8177, 1, -2, 8],
+    [-3, -15, 8210, -3, -1, 8],
+    [-11, 5, 8185, -3, -1, 10],
+    [-18, 13, 8176, -2, -3, 8],
+    [0, -3, 8173, -3, 0, 6],
+    [2, 8, 8202, 1, -2, 9],
+    [2, -5, 8212, 2, 2, 10],
+    [3, 2, 8211, -1, 1, 8],
+    [1, 17, 8199, 1, -2, 10],
+    [6, -16, 8202, -3, 0, 10],
+    [15, -3, 8186, -2, 1, 6],
+    [-15, 12, 8181, 1, 0, 8],
+    [-7, 18, 8188, -1, -3, 10],
+    [18, -15, 8207, -2, 3, 5],
+    [1, -7, 8188, -2, 1, 10],
+    [-4, -2, 8187, 1, -1, 8],
+    [10, -13, 8185, 0, -1, 10],
+    [0, 18, 8208, 2, -1, 10],
+    [14, -16, 8203, 1, -1, 10],
+    [-3, -13, 8198, 0, -2, 9],
+    [13, -4, 8180, 0, -2, 10],
+    [10, 3, 8190, -2, 2, 5],
+    [-10, -7, 8184, 1, 2, 6],
+    [6, -19, 8176, -1, 2, 5],
+    [19, 17, 8196, 1, -1, 9],
+    [-14, 4, 8189, -1, -2, 9],
+    [14, -19, 8210, 0, -1, 7],
+    [-8, 19, 8188, 2, -2, 10],
+    [12, 16, 8195, 3, -1, 6],
+    [-3, 7, 8206, -2, 1, 9],
+    [-12, 1, 8173, 2, -3, 10],
+    [-4, -11, 8201, 1, 2, 10],
+    [-16, 8, 8196, 0, 0, 6],
+    [-15, 7, 8178, 3, -2, 8],
+    [5, -14, 8208, 0, -1, 7],
+    [17, 7, 8179, 2, 1, 7],
+    [-6, 15, 8208, 1, 1, 7],
+    [-3, -17, 8193, 1, -1, 6],
+    [14, 6, 8174, -2, 1, 9],
+    [-7, 12, 8194, -1, -2, 11],
+    [-1, -12, 8179, 2, 3, 7],
+    [2, 17, 8173, 3, 0, 8],
+    [19, 10, 8187, 1, 0, 9],
+    [5, 17, 8191, -1, 0, 9],
+    [-19, 4, 8201, 0, -1, 10],
+    [-7, 16, 8208, -2, -1, 9],
+    [16, -4, 8177, 1, 2, 9],
+    [7, 18, 8206, 2, 0, 8],
+    [13, 12, 8210, -1, 3, 5],
+    [-10, -17, 8178, 0, 3, 9],
+    [-20, 19, 8210, 1, -3, 9],
+    [17, -12, 8202, -1, 0, 6],
+    [14, 19, 8176, 0, 2, 5],
+    [17, 3, 8201, 2, -1, 7],
+    [-5, -9, 8192, 1, 3, 6],
+    [-1, 20, 8203, -1, 3, 11],
+    [2, -10, 8195, 1, -3, 5],
+    [13, -9, 8207, 2, -3, 7],
+    [-12, -1, 8182, 1, -1, 6],
+    [6, -8, 8194, -1, 1, 6],
+    [2, -18, 8192, -1, 2, 9],
+    [-4, -12, 8174, 2, 1, 7],
+    [10, 20, 8209, 1, -3, 8],
+    [8, 19, 8202, -2, -1, 7],
+    [9, -8, 8201, -1, 2, 6],
+    [11, 15, 8211, -1, -2, 8],
+    [2, -18, 8211, -3, -1, 7],
+    [-14, -15, 8195, 1, 2, 8],
+    [5, 5, 8176, 0, 2, 9],
+    [-3, -3, 8187, 2, -2, 9],
+    [-18, -8, 8204, 0, 0, 9],
+    [-7, -10, 8187, 2, 0, 9],
+    [-8, -9, 8184, -1, 1, 10],
+    [8, 15, 8204, 2, -2, 6],
+    [6, -10, 8175, 0, 1, 8],
+    [-8, -12, 8206, -1, -1, 6],
+    [-1, 19, 8206, -2, -3, 6],
+    [-9, 16, 8208, -3, 3, 6],
+    [-20, 0, 8189, 2, 3, 11],
+    [-4, -14, 8196, -2, -2, 9],
+    [-13, 9, 8196, -2, -1, 7],
+    [-6, 10, 8184, -2, -1, 6],
+    [15, -19, 8206, 1, -2, 7],
+    [9, -7, 8207, -3, -3, 11],
+    [4, -5, 8195, 1, 3, 11],
+    [19, 18, 8190, 1, -2, 8],
+    [-14, -16, 8210, -2, 0, 9],
+    [-6, 5, 8210, -3, -2, 10],
+    [-3, -15, 8183, -3, -1, 9],
+    [13, 15, 8182, -1, -3, 10],
+    [-1, -6, 8176, -1, -2, 11],
+    [13, -12, 8199, -2, 1, 11],
+    [3, 13, 8177, -1, 2, 9],
+    [-4, -10, 8201, 1, 3, 7],
+    [19, -3, 8196, 3, 0, 8],
+    [18, 13, 8194, 0, 0, 7],
+    [-11, -6, 8196, -1, 2, 11],
+    [3, -6, 8185, 0, 2, 10],
+    [8, 12, 8184, 1, 0, 6],
+    [-18, -16, 8173, 1, -1, 6],
+    [-8, 4, 8192, 1, -2, 6],
+    [-3, -4, 8189, 2, -2, 9],
+    [-18, -11, 8202, 2, 0, 7],
+    [0, 8, 8184, -1, 1, 10],
+    [-12, -2, 8197, -2, -1, 5],
+    [6, -2, 8183, 1, 0, 6],
+    [17, -10, 8194, 3, 2, 9],
+    [13, -5, 8190, 2, 2, 7],
+    [-16, 13, 8211, 0, -3, 10],
+    [-17, 2, 8185, -2, 1, 9],
+    [-16, -5, 8177, 2, 0, 6],
+    [18, 10, 8210, 3, 2, 10],
+    [-3, -13, 8178, -2, 2, 6],
+    [11, -6, 8210, -2, 3, 8],
+    [14, -2, 8208, 1, 0, 8],
+    [16, -12, 8191, 1, 2, 7],
+    [13, -9, 8181, -2, 3, 8],
+    [1, -19, 8190, -2, -1, 10],
+    [-12, -2, 8206, 2, -2, 10],
+    [19, -1, 8183, -2, -2, 8],
+    [2, 4, 8211, 1, -3, 6],
+    [-1, 8, 8194, 2, 2, 7],
+    [-3, 7, 8187, -1, -2, 7],
+    [-10, -12, 8199, 0, 1, 6],
+    [20, 13, 8190, 1, 0, 9],
+    [-4, 20, 8178, 3, -1, 5],
+    [15, -8, 8196, -3, 2, 10],
+    [-10, -12, 8179, 3, -1, 10],
+    [18, 15, 8210, -2, 1, 10],
+    [-12, -8, 8196, -3, 0, 8],
+    [-11, -8, 8176, 1, -1, 7],
+    [-5, 17, 8204, 3, 0, 5],
+    [16, -13, 8190, 1, 2, 8],
+    [12, -18, 8181, -2, -2, 7],
+    [-13, 12, 8203, 0, -1, 10],
+    [-5, -1, 8204, -2, -1, 6],
+    [16, 6, 8179, -3, -2, 10],
+    [-1, 8, 8182, -1, 0, 9],
+    [-4, 2, 8190, -2, 0, 8],
+    [-6, 7, 8209, 0, -3, 8],
+    [10, -2, 8206, 2, -2, 7],
+    [-3, 13, 8178, 3, 2, 6],
+    [16, -8, 8192, 0, -1, 5],
+    [-15, 16, 8200, 0, -1, 9],
+    [4, 14, 8174, 1, -2, 8],
+    [18, 12, 8184, 2, 1, 9],
+    [14, 17, 8178, 0, -2, 9],
+    [18, 18, 8195, 0, 1, 9],
+    [13, 12, 8178, -2, -3, 5],
+    [8, 6, 8175, -2, 0, 11],
+    [5, -2, 8207, 3, 1, 8],
+    [4, 5, 8209, 0, 0, 7],
+    [-6, -7, 8191, -3, -1, 5],
+    [4, 0, 8183, 2, -3, 7],
+    [-5, -19, 8189, 0, 2, 8],
+    [12, -10, 8207, 0, 0, 7],
+    [-1, -18, 8208, 1, -2, 11],
+    [17, -13, 8211, 2, 2, 5],
+    [16, 7, 8174, -3, 0, 6],
+    [-13, -18, 8178, 0, 0, 6],
+    [9, -8, 8208, -2, 2, 7],
+    [15, -5, 8199, 0, 3, 7],
+    [12, -18, 8178, -3, 0, 11],
+    [-3, 0, 8195, 1, -2, 9],
+    [-2, -8, 8202, 3, 0, 10],
+    [-2, 19, 8207, -2, 1, 6],
+    [-8, 15, 8186, 0, -2, 7],
+    [16, -5, 8204, -2, -2, 9],
+    [4, -14, 8191, -3, -1, 10],
+    [9, -14, 8172, -3, 2, 10],
+    [-18, -1, 8207, 3, -1, 6],
+    [1, 13, 8185, -1, 1, 6],
+    [14, 6, 8199, -2, 1, 7],
+    [5, -12, 8204, 2, -3, 5],
+    [6, -19, 8187, -3, -2, 6],
+    [-7, -8, 8179, -1, 3, 7],
+    [-8, -19, 8180, 1, 2, 9],
+    [12, 0, 8210, -2, -2, 11],
+    [-8, 7, 8175, -2, 3, 9],
+    [18, -13, 8198, 0, 0, 5],
+    [-7, 6, 8210, 2, 1, 6],
+    [-15, -14, 8177, -2, 3, 6],
+    [-18, 9, 8181, -2, 1, 6],
+    [15, 4, 8196, 0, 1, 8],
+    [14, -5, 8174, 2, -3, 9],
+    [0, 3, 8191, 3, -1, 6],
+    [-14, 0, 8178, 0, -2, 6],
+    [15, 14, 8178, -3, 1, 5],
+    [-14, -18, 8192, -1, 2, 7],
+    [19, 19, 8190, -1, 3, 7],
+    [-18, -18, 8191, -1, -3, 9],
+    [14, 12, 8194, -1, -2, 6],
+    [16, 5, 8176, 3, 1, 10],
+    [-9, 7, 8210, 0, -1, 11],
+    [5, 19, 8200, 0, 2, 8],
+    [20, -13, 8198, -1, 1, 8],
+    [-1, -4, 8204, -2, 0, 9],
+    [8, -9, 8209, 0, -1, 11],
+    [12, 16, 8188, -3, 0, 7],
+    [1, 8, 8199, -1, -3, 7],
+    [19, 15, 8194, 1, -1, 11],
+    [-1, 12, 8183, -2, -1, 10],
+    [-1, 15, 8192, 1, 2, 11],
+    [1, -15, 8184, -3, 2, 9],
+    [-9, 8, 8178, -1, 3, 9],
+    [-4, 5, 8183, 3, 1, 6],
+    [-17, 11, 8176, 0, -2, 7],
+    [-6, -15, 8177, 0, 1, 8],
+    [5, -4, 8212, 2, 0, 8],
+    [11, -7, 8175, 2, -2, 5],
+    [-14, -5, 8199, 1, 3, 9],
+    [-4, -19, 8198, 0, 1, 5],
+    [4, 18, 8201, 0, 3, 8],
+    [17, -5, 8210, -1, -2, 7],
+    [3, -2, 8196, 0, 0, 7],
+    [-16, -1, 8190, -2, -3, 8],
+    [-2, -6, 8192, -1, -1, 9],
+    [-19, 8, 8198, 3, -3, 8],
+    [20, 6, 8209, -1, 2, 9],
+    [-3, -20, 8206, 1, -2, 10],
+    [7, 14, 8200, 1, 1, 10],
+    [-18, -14, 8182, -1, 1, 9],
+    [13, -3, 8209, 3, -2, 5],
+    [13, -5, 8208, -1, 3, 5],
+    [-10, -6, 8200, 2, 0, 9],
+    [3, 15, 8184, -1, -1, 7],
+    [-6, 1, 8192, 1, 3, 7],
+    [-6, 13, 8201, 0, 2, 11],
+    [19, -5, 8189, -1, 2, 10],
+    [-2, 0, 8191, 1, 3, 7],
+    [0, 8, 8187, -1, 0, 6],
+    [-10, -10, 8189, -1, 1, 6],
+    [-9, -14, 8209, -2, -3, 10],
+    [-8, -13, 8186, -2, -3, 10],
+    [9, -18, 8188, -2, 1, 5],
+    [19, -8, 8210, 1, -1, 7],
+    [-3, -3, 8194, -1, 0, 6],
+    [-20, 3, 8211, 2, 1, 11],
+    [-2, -5, 8190, 0, 0, 11],
+    [3, -17, 8207, 0, 3, 6],
+    [-15, -12, 8186, -3, 2, 8],
+    [12, 8, 8183, 2, -2, 7],
+    [12, -10, 8192, 3, 3, 10],
+    [-6, -17, 8176, -1, 1, 8],
+    [-2, -3, 8174, -2, -1, 9],
+    [10, 6, 8186, -3, 0, 9],
+    [12, -12, 8178, 1, -1, 8],
+    [17, 12, 8178, 3, -1, 11],
+    [-16, -5, 8176, 1, 2, 8],
+    [-3, -16, 8205, 0, -3, 8],
+    [10, 2, 8197, 2, 2, 9],
+    [14, -11, 8206, -2, -1, 9],
+    [-13, -19, 8190, 0, 3, 6],
+    [-14, 15, 8196, -1, 0, 11],
+    [-12, -9, 8212, 1, 3, 8],
+    [-10, -12, 8182, 2, 2, 10],
+    [-13, -3, 8188, 1, 1, 6],
+    [-2, -14, 8178, 3, -2, 6],
+    [-13, 5, 8205, -2, 0, 10],
+    [-9, 12, 8174, 2, 1, 10],
+    [-1, 5, 8211, -1, 1, 7],
+    [-16, 11, 8175, 2, -2, 6],
+    [12, 7, 8180, 0, 3, 10],
+    [1, -6, 8188, 0, 1, 5],
+    [-13, 13, 8199, -2, -3, 7],
+    [-10, -7, 8172, 0, 0, 11],
+    [-8, 13, 8181, 2, -1, 10],
+    [-17, 2, 8208, -2, -1, 10],
+    [-13, -17, 8194, 1, 2, 5],
+    [-12, -20, 8207, 1, 3, 6],
+    [-2, -19, 8202, 1, -1, 5],
+    [14, -8, 8181, 0, 0, 9],
+    [16, -13, 8199, 2, 0, 7],
+    [-6, 1, 8201, 2, -1, 8],
+    [-12, 18, 8192, -3, -3, 6],
+    [-7, -1, 8211, 0, 1, 6],
+    [-15, -12, 8173, -1, 3, 5],
+    [8, 13, 8200, -2, 0, 10],
+    [15, -14, 8196, 2, 0, 6],
+    [6, 6, 8208, 2, 2, 9],
+    [-8, -19, 8197, -2, -3, 10],
+    [2, 5, 8192, -2, 2, 11],
+    [8, 12, 8198, 2, 2, 7],
+    [-19, -1, 8193, 3, -2, 5],
+    [-8, 4, 8182, -1, 2, 9],
+    [10, 15, 8179, -1, 1, 5],
+    [6, 7, 8202, -2, 0, 9],
+    [8, -10, 8179, 2, 1, 10],
+    [-2, -6, 8185, 1, 2, 10],
+    [-14, -15, 8187, 0, 1, 7],
+    [9, 10, 8187, -3, 0, 6],
+    [-18, -19, 8187, 1, 1, 8],
+    [-20, -16, 8183, -2, 1, 10],
+    [1, 8, 8207, 2, 0, 6],
+    [1, 10, 8185, 0, 2, 10],
+    [-7, 13, 8194, 0, 3, 6],
+    [16, -11, 8184, 2, -1, 11],
+    [16, 11, 8191, 0, 2, 9],
+    [-7, 2, 8177, -1, -1, 6],
+    [-2, 7, 8182, -2, 1, 6],
+    [18, -2, 8187, -1, -2, 8],
+    [-11, -5, 8190, -2, 1, 6],
+    [-6, 1, 8205, 0, -2, 5],
+    [-1, 0, 8191, -2, 2, 8],
+    [2, 1, 8207, -3, 3, 9],
+    [-6, -12, 8175, 1, 3, 5],
+    [9, 9, 8179, 1, -2, 10],
+    [-5, 13, 8203, -3, 2, 11],
+    [-5, 19, 8182, 1, -1, 8],
+    [-4, -11, 8204, 0, -1, 5],
+    [-5, 16, 8178, 1, 1, 5],
+    [17, 2, 8186, -3, -2, 8],
+    [19, -3, 8186, 0, 0, 10],
+    [9, 0, 8200, 3, 3, 10],
+    [6, -3, 8205, -3, 2, 9],
+    [-5, -9, 8200, 2, -3, 9],
+    [-12, -15, 8174, 3, 0, 11],
+    [16, -5, 8181, -1, 0, 5],
+    [-12, -16, 8201, -2, 1, 9],
+    [-10, 17, 8200, -1, 0, 10],
+    [-2, -17, 8178, -1, 2, 10],
+    [-8, 18, 8187, 2, 3, 9],
+    [-11, 14, 8190, 1, -2, 11],
+    [-1, 12, 8208, 0, -1, 8],
+    [-17, -12, 8200, 1, -1, 6],
+    [-17, -8, 8187, 3, 2, 6],
+    [-17, -10, 8200, 2, 2, 7],
+    [-15, 17, 8185, 1, -2, 5],
+    [-15, -9, 8205, 0, -1, 10],
+    [-7, 1, 8194, 1, 1, 9],
+    [-9, 20, 8200, -2, 1, 10],
+    [-10, 16, 8196, 2, -2, 5],
+    [5, 14, 8203, 2, -3, 10],
+    [15, 6, 8197, -2, 3, 10],
+    [16, -15, 8211, 0, -3, 9],
+    [-6, 14, 8189, -1, 2, 5],
+    [-16, -20, 8186, -1, 3, 6],
+    [-11, 4, 8190, -3, -3, 9],
+    [-6, -10, 8195, -2, -2, 5],
+    [20, -9, 8191, 0, 2, 7],
+    [0, 15, 8205, 1, 0, 7],
+    [-20, 9, 8172, 0, 1, 6],
+];
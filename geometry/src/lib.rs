@@ -0,0 +1,151 @@
+//! Hardware independent LED layout maths used by the [`tildagon`](https://crates.io/crates/tildagon) crate.
+//!
+//! Angles are in degrees, measured clockwise from the top of the badge (with the front board facing the viewer).
+//!
+//! This is separate from the board support crate so that it builds and is tested on the host with `cargo test` from
+//! this directory.
+
+#![cfg_attr(not(test), no_std)]
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PixelPosition {
+    /// Angle clockwise from the top of the badge, in degrees
+    pub angle: u16,
+
+    /// Distance from the centre of the badge, in millimetres
+    pub radius: u8,
+}
+
+/// The positions either side of an angle, see [`neighbours_of_angle`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AngleNeighbours {
+    pub counter_clockwise: usize,
+
+    /// The position exactly at the angle, if there is one
+    pub at: Option<usize>,
+
+    pub clockwise: usize,
+}
+
+/// The smallest difference between two angles, in degrees.
+pub fn angular_distance(a: u16, b: u16) -> u16 {
+    let d = (a % 360).abs_diff(b % 360);
+    d.min(360 - d)
+}
+
+/// Index of the position closest to an angle.
+pub fn closest_to_angle(positions: &[PixelPosition], angle: u16) -> Option<usize> {
+    positions
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, p)| angular_distance(p.angle, angle))
+        .map(|(i, _)| i)
+}
+
+/// Indices of the positions immediately either side of an angle.
+///
+/// If a position is exactly at the angle it is returned in [`AngleNeighbours::at`], and the neighbours are the
+/// positions either side of that one.
+///
+/// Assumes the positions are evenly spaced in clockwise order, as is the case for an LED ring.
+pub fn neighbours_of_angle(positions: &[PixelPosition], angle: u16) -> Option<AngleNeighbours> {
+    let n = positions.len();
+    let closest = closest_to_angle(positions, angle)?;
+
+    if angular_distance(positions[closest].angle, angle) == 0 {
+        return Some(AngleNeighbours {
+            counter_clockwise: (closest + n - 1) % n,
+            at: Some(closest),
+            clockwise: (closest + 1) % n,
+        });
+    }
+
+    let clockwise_of_closest = (angle % 360 + 360 - positions[closest].angle % 360) % 360 < 180;
+    let (counter_clockwise, clockwise) = match clockwise_of_closest {
+        true => (closest, (closest + 1) % n),
+        false => ((closest + n - 1) % n, closest),
+    };
+    Some(AngleNeighbours {
+        counter_clockwise,
+        at: None,
+        clockwise,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ring of 12 evenly spaced positions, the first at the top
+    fn ring() -> Vec<PixelPosition> {
+        (0..12)
+            .map(|i| PixelPosition {
+                angle: i * 30,
+                radius: 30,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn angular_distance_wraps() {
+        assert_eq!(angular_distance(10, 350), 20);
+        assert_eq!(angular_distance(350, 10), 20);
+        assert_eq!(angular_distance(0, 180), 180);
+        assert_eq!(angular_distance(90, 450), 0);
+    }
+
+    #[test]
+    fn closest_to_angle_wraps() {
+        let ring = ring();
+        assert_eq!(closest_to_angle(&ring, 40), Some(1));
+        assert_eq!(closest_to_angle(&ring, 350), Some(0));
+        assert_eq!(closest_to_angle(&[], 0), None);
+    }
+
+    #[test]
+    fn neighbours_between_positions() {
+        let ring = ring();
+        let expected = AngleNeighbours {
+            counter_clockwise: 1,
+            at: None,
+            clockwise: 2,
+        };
+        assert_eq!(neighbours_of_angle(&ring, 40), Some(expected));
+        assert_eq!(neighbours_of_angle(&ring, 50), Some(expected));
+
+        let expected = AngleNeighbours {
+            counter_clockwise: 11,
+            at: None,
+            clockwise: 0,
+        };
+        assert_eq!(neighbours_of_angle(&ring, 345), Some(expected));
+    }
+
+    #[test]
+    fn neighbours_include_exact_match() {
+        let ring = ring();
+        assert_eq!(
+            neighbours_of_angle(&ring, 60),
+            Some(AngleNeighbours {
+                counter_clockwise: 1,
+                at: Some(2),
+                clockwise: 3,
+            })
+        );
+        assert_eq!(
+            neighbours_of_angle(&ring, 0),
+            Some(AngleNeighbours {
+                counter_clockwise: 11,
+                at: Some(0),
+                clockwise: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn neighbours_of_nothing() {
+        assert_eq!(neighbours_of_angle(&[], 0), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::pins::{ExpanderPin, PinError};
+use defmt::Format;
+use embedded_aw9523::{Input, Output, async_traits::digital::OutputPin};
+use embedded_hal::digital::PinState;
+
+#[derive(Debug, Format)]
+pub enum BacklightError<E, P> {
+    I2c(E),
+    Pin(P),
+}
+
+enum BacklightControl<I2C> {
+    Fixed,
+    Switched(Output<I2C>),
+    Dimmable {
+        i2c: I2C,
+        pin: ExpanderPin,
+        _claim: Input<I2C>,
+    },
+}
+
+/// Display backlight, controlled in whichever way the front board allows.
+pub struct Backlight<I2C> {
+    control: BacklightControl<I2C>,
+    brightness: u8,
+}
+
+impl<I2C, E> Backlight<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// A backlight that is permanently on.
+    pub fn fixed() -> Self {
+        Self {
+            control: BacklightControl::Fixed,
+            brightness: 255,
+        }
+    }
+
+    /// A backlight switched on and off by an expander output, any non-zero brightness turns it on.
+    pub fn switched(pin: Output<I2C>) -> Self {
+        Self {
+            control: BacklightControl::Switched(pin),
+            brightness: 255,
+        }
+    }
+
+    /// A backlight driven by the constant current LED mode of an expander pin (e.g. one of
+    /// [`FrontBoardPins`](crate::pins::FrontBoardPins)).
+    ///
+    /// The pin is held to prevent it being used for anything else.
+    pub async fn dimmable(mut i2c: I2C, claim: Input<I2C>) -> Result<Self, E> {
+        let pin = ExpanderPin::of(&claim);
+        pin.modify(&mut i2c, pin.led_mode_register(), false).await?;
+        i2c.write(pin.address, &[pin.dim_register(), 255]).await?;
+
+        Ok(Self {
+            control: BacklightControl::Dimmable {
+                i2c,
+                pin,
+                _claim: claim,
+            },
+            brightness: 255,
+        })
+    }
+
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// True if the brightness can be set to more than just on and off.
+    pub fn is_dimmable(&self) -> bool {
+        matches!(self.control, BacklightControl::Dimmable { .. })
+    }
+
+    pub async fn set_brightness(
+        &mut self,
+        brightness: u8,
+    ) -> Result<(), BacklightError<E, PinError<I2C>>> {
+        self.brightness = brightness;
+        self.write().await
+    }
+
+    pub async fn on(&mut self) -> Result<(), BacklightError<E, PinError<I2C>>> {
+        self.set_brightness(255).await
+    }
+
+    pub async fn off(&mut self) -> Result<(), BacklightError<E, PinError<I2C>>> {
+        self.set_brightness(0).await
+    }
+
+    async fn write(&mut self) -> Result<(), BacklightError<E, PinError<I2C>>> {
+        match &mut self.control {
+            BacklightControl::Fixed => Ok(()),
+            BacklightControl::Switched(output) => output
+                .set_state(match self.brightness {
+                    0 => PinState::Low,
+                    _ => PinState::High,
+                })
+                .await
+                .map_err(BacklightError::Pin),
+            BacklightControl::Dimmable { i2c, pin, .. } => i2c
+                .write(pin.address, &[pin.dim_register(), self.brightness])
+                .await
+                .map_err(BacklightError::I2c),
+        }
+    }
+}
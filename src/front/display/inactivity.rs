@@ -0,0 +1,178 @@
+//! Dims and then sleeps the display when the badge has not been used for a while.
+
+use super::{Backlight, BacklightError};
+use crate::{
+    button_collection::ButtonEvent,
+    front::emf2024::{AsyncDisplay, AsyncDisplayError},
+    pins::PinError,
+};
+use defmt::{Format, debug};
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::SpiDevice;
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct InactivityConfig {
+    /// Time without activity before the backlight is dimmed
+    pub dim_after: Duration,
+
+    /// Time without activity before the display is put to sleep
+    pub sleep_after: Duration,
+
+    /// Backlight brightness when active
+    pub brightness: u8,
+
+    /// Backlight brightness when dimmed
+    pub dim_brightness: u8,
+}
+
+impl Default for InactivityConfig {
+    fn default() -> Self {
+        Self {
+            dim_after: Duration::from_secs(30),
+            sleep_after: Duration::from_secs(60),
+            brightness: 255,
+            dim_brightness: 32,
+        }
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayPowerState {
+    Active,
+    Dimmed,
+    Asleep,
+}
+
+#[derive(Debug, Format)]
+pub enum DisplayPowerError<D, B> {
+    Display(D),
+    Backlight(B),
+}
+
+/// Tracks activity and decides when the display should be dimmed or put to sleep.
+///
+/// Feed it button events (and any other activity via [`InactivityTimer::activity`]), wait until
+/// [`InactivityTimer::deadline`], then call [`InactivityTimer::update`]. Whenever the state changes it should be applied
+/// to the display with [`InactivityTimer::apply`].
+pub struct InactivityTimer {
+    config: InactivityConfig,
+    last_activity: Instant,
+    state: DisplayPowerState,
+    applied: DisplayPowerState,
+}
+
+impl InactivityTimer {
+    pub fn new(config: InactivityConfig) -> Self {
+        Self {
+            config,
+            last_activity: Instant::now(),
+            state: DisplayPowerState::Active,
+            applied: DisplayPowerState::Active,
+        }
+    }
+
+    pub fn config(&self) -> &InactivityConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> DisplayPowerState {
+        self.state
+    }
+
+    /// Record activity at a given time, returning the new state if the display should be woken.
+    pub fn activity(&mut self, time: Instant) -> Option<DisplayPowerState> {
+        self.last_activity = self.last_activity.max(time);
+        self.transition(DisplayPowerState::Active)
+    }
+
+    /// Record a button event as activity.
+    ///
+    /// Returns the new state if the display should be woken, in which case the application may want to ignore the
+    /// button press, as the user could not see what it would do.
+    pub fn handle_button_event<B>(&mut self, event: &ButtonEvent<B>) -> Option<DisplayPowerState> {
+        self.activity(*event.now().time())
+    }
+
+    /// The next time at which [`InactivityTimer::update`] may change state.
+    pub fn deadline(&self) -> Option<Instant> {
+        match self.state {
+            DisplayPowerState::Active => Some(self.last_activity + self.config.dim_after),
+            DisplayPowerState::Dimmed => Some(self.last_activity + self.config.sleep_after),
+            DisplayPowerState::Asleep => None,
+        }
+    }
+
+    /// Check for inactivity, returning the new state if the display should be dimmed or put to sleep.
+    pub fn update(&mut self, now: Instant) -> Option<DisplayPowerState> {
+        let idle = now.saturating_duration_since(self.last_activity);
+
+        let state = if idle >= self.config.sleep_after {
+            DisplayPowerState::Asleep
+        } else if idle >= self.config.dim_after {
+            DisplayPowerState::Dimmed
+        } else {
+            DisplayPowerState::Active
+        };
+
+        self.transition(state)
+    }
+
+    /// Put the display and backlight into the current state.
+    pub async fn apply<SPI, DC, I2C, E>(
+        &mut self,
+        display: &mut AsyncDisplay<SPI, DC>,
+        backlight: &mut Backlight<I2C>,
+    ) -> Result<
+        (),
+        DisplayPowerError<
+            AsyncDisplayError<SPI::Error, DC::Error>,
+            BacklightError<E, PinError<I2C>>,
+        >,
+    >
+    where
+        SPI: SpiDevice,
+        DC: OutputPin,
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let state = self.state;
+
+        match state {
+            DisplayPowerState::Active | DisplayPowerState::Dimmed => {
+                if self.applied == DisplayPowerState::Asleep {
+                    display.wake().await.map_err(DisplayPowerError::Display)?;
+                }
+
+                backlight
+                    .set_brightness(match state {
+                        DisplayPowerState::Active => self.config.brightness,
+                        _ => self.config.dim_brightness,
+                    })
+                    .await
+                    .map_err(DisplayPowerError::Backlight)?;
+            }
+            DisplayPowerState::Asleep => {
+                if self.applied != DisplayPowerState::Asleep {
+                    backlight
+                        .off()
+                        .await
+                        .map_err(DisplayPowerError::Backlight)?;
+                    display.sleep().await.map_err(DisplayPowerError::Display)?;
+                }
+            }
+        }
+
+        self.applied = state;
+        Ok(())
+    }
+
+    fn transition(&mut self, state: DisplayPowerState) -> Option<DisplayPowerState> {
+        if state == self.state {
+            return None;
+        }
+
+        debug!("Display power state {} -> {}", self.state, state);
+        self.state = state;
+        Some(state)
+    }
+}
@@ -0,0 +1,7 @@
+mod backlight;
+mod inactivity;
+mod round;
+
+pub use backlight::*;
+pub use inactivity::*;
+pub use round::*;
@@ -0,0 +1,308 @@
+//! Drawing helpers for circular displays.
+//!
+//! Angles are in degrees, clockwise from the top of the display, the same as for the LED geometry.
+
+use embedded_graphics::{
+    Drawable,
+    draw_target::DrawTarget,
+    geometry::{Angle, Dimensions, Point, Size},
+    mono_font::MonoTextStyle,
+    pixelcolor::PixelColor,
+    primitives::{Arc, Primitive, PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use micromath::F32Ext;
+
+/// Wraps a square draw target, only drawing pixels that fall within its inscribed circle.
+pub struct RoundDisplay<D> {
+    inner: D,
+}
+
+impl<D> RoundDisplay<D>
+where
+    D: DrawTarget,
+{
+    pub fn new(inner: D) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// Mutable access to the wrapped draw target, drawing to this is not clipped.
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// Diameter of the visible circle.
+    pub fn diameter(&self) -> u32 {
+        let size = self.inner.bounding_box().size;
+        size.width.min(size.height)
+    }
+
+    /// The pixel at the centre of the visible circle.
+    pub fn centre(&self) -> Point {
+        self.inner.bounding_box().center()
+    }
+
+    /// True if a pixel is within the visible circle.
+    pub fn is_visible(&self, point: Point) -> bool {
+        let (dx, dy) = self.doubled_offset(point);
+        let d = self.diameter() as i64;
+        dx * dx + dy * dy <= d * d
+    }
+
+    /// The largest rectangle that is entirely visible, inset by `margin` pixels on each side.
+    ///
+    /// Text and other rectangular content placed in here is never clipped by the edge of the display.
+    pub fn content_area(&self, margin: u32) -> Rectangle {
+        let side = (self.diameter() as f32 * core::f32::consts::FRAC_1_SQRT_2) as u32;
+        let side = side.saturating_sub(margin * 2);
+        Rectangle::with_center(self.centre(), Size::new(side, side))
+    }
+
+    /// The point at a given angle and distance from the centre.
+    pub fn point_at(&self, angle: f32, radius: f32) -> Point {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (cx, cy) = self.exact_centre();
+        Point::new(
+            (cx + radius * sin).round() as i32,
+            (cy - radius * cos).round() as i32,
+        )
+    }
+
+    /// The angle (0 to 360) and distance from the centre of a point.
+    pub fn polar_of(&self, point: Point) -> (f32, f32) {
+        let (cx, cy) = self.exact_centre();
+        let dx = point.x as f32 - cx;
+        let dy = point.y as f32 - cy;
+
+        let angle = dx.atan2(-dy).to_degrees();
+        let angle = if angle < 0.0 { angle + 360.0 } else { angle };
+
+        (angle, dx.hypot(dy))
+    }
+
+    /// Draw an arc following the edge of the display.
+    ///
+    /// The arc is `thickness` pixels wide and starts `inset` pixels in from the edge.
+    pub fn draw_arc(
+        &mut self,
+        start: f32,
+        sweep: f32,
+        inset: u32,
+        thickness: u32,
+        colour: D::Color,
+    ) -> Result<(), D::Error> {
+        // Arcs are stroked either side of their circle
+        let diameter = self
+            .diameter()
+            .saturating_sub(inset * 2)
+            .saturating_sub(thickness);
+
+        Arc::with_center(
+            self.centre(),
+            diameter,
+            Angle::from_degrees(start - 90.0),
+            Angle::from_degrees(sweep),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(colour, thickness))
+        .draw(self)
+    }
+
+    /// Draw text centred on an angle, with each character placed along a circle of `radius`.
+    ///
+    /// Characters are not rotated, so this reads best near the top and bottom of the display.
+    /// Text across the top should use [`ArcTextDirection::Clockwise`] and across the bottom
+    /// [`ArcTextDirection::Anticlockwise`] to read left to right.
+    pub fn draw_text_on_arc(
+        &mut self,
+        text: &str,
+        style: MonoTextStyle<'_, D::Color>,
+        angle: f32,
+        radius: f32,
+        direction: ArcTextDirection,
+    ) -> Result<(), D::Error> {
+        if radius <= 0.0 {
+            return Ok(());
+        }
+
+        let advance = (style.font.character_size.width + style.font.character_spacing) as f32;
+        let step = (advance / radius).to_degrees();
+        let step = match direction {
+            ArcTextDirection::Clockwise => step,
+            ArcTextDirection::Anticlockwise => -step,
+        };
+
+        let count = text.chars().count();
+        let first = angle - step * (count.saturating_sub(1)) as f32 / 2.0;
+
+        let text_style = TextStyleBuilder::new()
+            .alignment(Alignment::Center)
+            .baseline(Baseline::Middle)
+            .build();
+
+        for (i, c) in text.chars().enumerate() {
+            let mut buffer = [0u8; 4];
+            let position = self.point_at(first + step * i as f32, radius);
+            Text::with_text_style(c.encode_utf8(&mut buffer), position, style, text_style)
+                .draw(self)?;
+        }
+
+        Ok(())
+    }
+
+    /// Offset of a pixel centre from the centre of the display, in half pixels.
+    fn doubled_offset(&self, point: Point) -> (i64, i64) {
+        let area = self.inner.bounding_box();
+        (
+            (2 * (point.x - area.top_left.x) + 1 - area.size.width as i32) as i64,
+            (2 * (point.y - area.top_left.y) + 1 - area.size.height as i32) as i64,
+        )
+    }
+
+    fn exact_centre(&self) -> (f32, f32) {
+        let area = self.inner.bounding_box();
+        (
+            area.top_left.x as f32 + (area.size.width as f32 - 1.0) / 2.0,
+            area.top_left.y as f32 + (area.size.height as f32 - 1.0) / 2.0,
+        )
+    }
+}
+
+impl<D> Dimensions for RoundDisplay<D>
+where
+    D: DrawTarget,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.inner.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for RoundDisplay<D>
+where
+    D: DrawTarget,
+{
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = embedded_graphics::Pixel<Self::Color>>,
+    {
+        let area = self.inner.bounding_box();
+        let d = self.diameter() as i64;
+        let w = area.size.width as i32;
+        let h = area.size.height as i32;
+        let tl = area.top_left;
+
+        self.inner.draw_iter(pixels.into_iter().filter(|p| {
+            let dx = (2 * (p.0.x - tl.x) + 1 - w) as i64;
+            let dy = (2 * (p.0.y - tl.y) + 1 - h) as i64;
+            dx * dx + dy * dy <= d * d
+        }))
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let bounds = self.inner.bounding_box();
+        let area = area.intersection(&bounds);
+        let d = self.diameter() as i64;
+        let w = bounds.size.width as i32;
+
+        // Fill the visible span of each row
+        for y in area.rows() {
+            let (_, dy) = self.doubled_offset(Point::new(bounds.top_left.x, y));
+            let Some(s) = (d * d - dy * dy).checked_isqrt() else {
+                continue;
+            };
+            let s = s as i32;
+
+            let left = (bounds.top_left.x + (w - s).div_euclid(2)).max(area.top_left.x);
+            let right = (bounds.top_left.x + (w - 1 + s).div_euclid(2))
+                .min(area.top_left.x + area.size.width as i32 - 1);
+
+            if left <= right {
+                self.inner.fill_solid(
+                    &Rectangle::new(Point::new(left, y), Size::new((right - left + 1) as u32, 1)),
+                    color,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, defmt::Format, Clone, Copy, PartialEq, Eq)]
+pub enum ArcTextDirection {
+    Clockwise,
+    Anticlockwise,
+}
+
+/// A ring (or part of one) around the edge of the display, filled in proportion to a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RingGauge<C> {
+    /// Angle of the empty end of the gauge
+    pub start: f32,
+
+    /// Angle covered by a full gauge, negative to fill anticlockwise
+    pub sweep: f32,
+
+    /// Distance in from the edge of the display
+    pub inset: u32,
+
+    pub thickness: u32,
+
+    pub colour: C,
+
+    /// Colour of the unfilled part of the gauge, if it should be drawn
+    pub background: Option<C>,
+}
+
+impl<C> RingGauge<C>
+where
+    C: PixelColor,
+{
+    /// A full ring gauge, filling clockwise from the top.
+    pub fn new(colour: C) -> Self {
+        Self {
+            start: 0.0,
+            sweep: 360.0,
+            inset: 2,
+            thickness: 8,
+            colour,
+            background: None,
+        }
+    }
+
+    /// Draw the gauge filled to `value` (0.0 to 1.0).
+    pub fn draw<D>(&self, display: &mut RoundDisplay<D>, value: f32) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let filled = self.sweep * value.clamp(0.0, 1.0);
+
+        if let Some(background) = self.background {
+            if filled != self.sweep {
+                display.draw_arc(
+                    self.start + filled,
+                    self.sweep - filled,
+                    self.inset,
+                    self.thickness,
+                    background,
+                )?;
+            }
+        }
+
+        if filled != 0.0 {
+            display.draw_arc(self.start, filled, self.inset, self.thickness, self.colour)?;
+        }
+
+        Ok(())
+    }
+}
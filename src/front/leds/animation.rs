@@ -0,0 +1,301 @@
+//! Keyframed LED effects, composed by priority.
+//!
+//! Each [`AnimationTarget`] (the front ring, the base board LED, a hexpansion port LED or an LED strip on a hexpansion)
+//! renders the highest priority effect currently set on it, so a short lived status overlay can temporarily override
+//! an ambient effect. Targets with no effects are left untouched, so they can still be drawn by hand.
+
+use super::{BaseBoardLed, FrontLeds, HexpansionPortLed};
+use crate::hexpansions::HexpansionPort;
+use defmt::Format;
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+use smart_leds::{
+    RGB8,
+    hsv::{Hsv, hsv2rgb},
+};
+use strum::IntoEnumIterator;
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy)]
+pub enum AnimationTarget {
+    Front,
+    BaseBoard,
+    Hexpansion(HexpansionPort),
+
+    /// An LED strip carried on the hexpansion in a port, see [`Animator::render_hexpansion_strip`]
+    HexpansionStrip(HexpansionPort),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Effect {
+    /// A single colour
+    Solid(RGB8),
+
+    /// A rainbow across all pixels, rotating once per period
+    RainbowChase { period: Duration },
+
+    /// Fade a colour in and out, once per period
+    Breathe { colour: RGB8, period: Duration },
+
+    /// A block of pixels rotating once per period
+    Spinner {
+        colour: RGB8,
+        period: Duration,
+        width: usize,
+    },
+
+    /// A single pixel with a fading tail, rotating once per period
+    Comet {
+        colour: RGB8,
+        period: Duration,
+        tail: usize,
+    },
+
+    /// Light a proportion (`progress` / 255) of the pixels
+    ProgressRing {
+        colour: RGB8,
+        background: RGB8,
+        progress: u8,
+    },
+
+    /// Step through the first `length` bits of `pattern` (LSB first), lighting the pixels for each set bit
+    Blink {
+        colour: RGB8,
+        pattern: u32,
+        length: u8,
+        step: Duration,
+    },
+}
+
+impl Effect {
+    /// Render the effect as it appears `elapsed` after it was started.
+    pub fn render(&self, elapsed: Duration, out: &mut [RGB8]) {
+        let n = out.len();
+        if n == 0 {
+            return;
+        }
+
+        match *self {
+            Effect::Solid(colour) => out.fill(colour),
+            Effect::RainbowChase { period } => {
+                let offset = phase(elapsed, period, 256);
+                for (i, pixel) in out.iter_mut().enumerate() {
+                    *pixel = hsv2rgb(Hsv {
+                        hue: (offset + i * 256 / n) as u8,
+                        sat: 255,
+                        val: 255,
+                    });
+                }
+            }
+            Effect::Breathe { colour, period } => {
+                let p = phase(elapsed, period, 512);
+                let level = if p < 256 { p } else { 511 - p };
+                out.fill(scale(colour, level as u8));
+            }
+            Effect::Spinner {
+                colour,
+                period,
+                width,
+            } => {
+                let head = phase(elapsed, period, n);
+                out.fill(RGB8::default());
+                for i in 0..width.min(n) {
+                    out[(head + i) % n] = colour;
+                }
+            }
+            Effect::Comet {
+                colour,
+                period,
+                tail,
+            } => {
+                let head = phase(elapsed, period, n);
+                let tail = tail.min(n - 1);
+                out.fill(RGB8::default());
+                for i in 0..=tail {
+                    let level = 255 - (i * 255 / (tail + 1));
+                    out[(head + n - i) % n] = scale(colour, level as u8);
+                }
+            }
+            Effect::ProgressRing {
+                colour,
+                background,
+                progress,
+            } => {
+                // Progress in 1/255ths of a pixel
+                let lit = progress as usize * n;
+                for (i, pixel) in out.iter_mut().enumerate() {
+                    let fill = lit.saturating_sub(i * 255).min(255);
+                    *pixel = blend(background, colour, fill as u8);
+                }
+            }
+            Effect::Blink {
+                colour,
+                pattern,
+                length,
+                step,
+            } => {
+                let length = length.clamp(1, 32) as usize;
+                let bit = phase(elapsed, step * length as u32, length);
+                out.fill(if pattern & (1 << bit) != 0 {
+                    colour
+                } else {
+                    RGB8::default()
+                });
+            }
+        }
+    }
+}
+
+/// Position within a repeating period, scaled to `0..steps`.
+fn phase(elapsed: Duration, period: Duration, steps: usize) -> usize {
+    let period = period.as_ticks().max(1);
+    ((elapsed.as_ticks() % period) * steps as u64 / period) as usize
+}
+
+fn scale(colour: RGB8, level: u8) -> RGB8 {
+    let c = |c: u8| (c as u16 * level as u16 / 255) as u8;
+    RGB8::new(c(colour.r), c(colour.g), c(colour.b))
+}
+
+fn blend(a: RGB8, b: RGB8, amount: u8) -> RGB8 {
+    let c =
+        |a: u8, b: u8| ((a as u16 * (255 - amount) as u16 + b as u16 * amount as u16) / 255) as u8;
+    RGB8::new(c(a.r, b.r), c(a.g, b.g), c(a.b, b.b))
+}
+
+struct Layer {
+    target: AnimationTarget,
+    priority: u8,
+    effect: Effect,
+    started: Instant,
+    until: Option<Instant>,
+}
+
+/// Manages up to `L` effects across all targets.
+pub struct Animator<const L: usize> {
+    layers: Vec<Layer, L>,
+}
+
+impl<const L: usize> Default for Animator<L> {
+    fn default() -> Self {
+        Self { layers: Vec::new() }
+    }
+}
+
+impl<const L: usize> Animator<L> {
+    /// Set the effect for a target at a given priority, replacing any effect already at that priority.
+    ///
+    /// If `duration` is given the effect is removed once it has elapsed, revealing any lower priority effect.
+    /// Returns the effect back if there is no space for it.
+    pub fn set(
+        &mut self,
+        target: AnimationTarget,
+        priority: u8,
+        effect: Effect,
+        duration: Option<Duration>,
+    ) -> Result<(), Effect> {
+        self.clear(target, priority);
+
+        let started = Instant::now();
+        self.layers
+            .push(Layer {
+                target,
+                priority,
+                effect,
+                started,
+                until: duration.map(|d| started + d),
+            })
+            .map_err(|layer| layer.effect)
+    }
+
+    /// Update an effect in place without restarting it, e.g. to change the progress of a [`Effect::ProgressRing`].
+    pub fn update(&mut self, target: AnimationTarget, priority: u8, effect: Effect) -> bool {
+        match self
+            .layers
+            .iter_mut()
+            .find(|l| l.target == target && l.priority == priority)
+        {
+            Some(layer) => {
+                layer.effect = effect;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the effect for a target at a given priority.
+    pub fn clear(&mut self, target: AnimationTarget, priority: u8) {
+        self.layers
+            .retain(|l| !(l.target == target && l.priority == priority));
+    }
+
+    /// Remove all effects for a target.
+    pub fn clear_target(&mut self, target: AnimationTarget) {
+        self.layers.retain(|l| l.target != target);
+    }
+
+    /// Render the highest priority effect for a target.
+    ///
+    /// Returns false (leaving `out` untouched) if the target has no effects.
+    pub fn render_target(
+        &mut self,
+        target: AnimationTarget,
+        now: Instant,
+        out: &mut [RGB8],
+    ) -> bool {
+        self.layers
+            .retain(|l| l.until.is_none_or(|until| now < until));
+
+        match self
+            .layers
+            .iter()
+            .filter(|l| l.target == target)
+            .max_by_key(|l| l.priority)
+        {
+            Some(layer) => {
+                layer
+                    .effect
+                    .render(now.saturating_duration_since(layer.started), out);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Render all front board targets into a pixel buffer.
+    ///
+    /// Hexpansion strips are separate chains, so are rendered with [`Animator::render_hexpansion_strip`].
+    pub fn render<B>(&mut self, now: Instant, buffer: &mut B)
+    where
+        B: FrontLeds + BaseBoardLed + HexpansionPortLed,
+    {
+        self.render_target(AnimationTarget::Front, now, buffer.front());
+        self.render_target(
+            AnimationTarget::BaseBoard,
+            now,
+            core::slice::from_mut(buffer.base_board()),
+        );
+        for port in HexpansionPort::iter() {
+            self.render_target(
+                AnimationTarget::Hexpansion(port),
+                now,
+                core::slice::from_mut(buffer.hexpansion_port(port)),
+            );
+        }
+    }
+
+    /// Render the effect for the LED strip on a hexpansion into its pixels, e.g. those of a
+    /// [`HexpansionLedStrip`](crate::hexpansions::HexpansionLedStrip).
+    ///
+    /// Returns false (leaving the pixels untouched) if the strip has no effects.
+    pub fn render_hexpansion_strip<B>(
+        &mut self,
+        port: HexpansionPort,
+        now: Instant,
+        strip: &mut B,
+    ) -> bool
+    where
+        B: FrontLeds,
+    {
+        self.render_target(AnimationTarget::HexpansionStrip(port), now, strip.front())
+    }
+}
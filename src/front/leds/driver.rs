@@ -0,0 +1,194 @@
+//! Rendering of a [`PixelBuffer`](super::PixelBuffer) to a chain of WS2812 LEDs.
+//!
+//! Gamma correction, global brightness and current limiting are applied when the buffer is flushed, the buffer itself
+//! always holds the colours as the application intends them.
+
+use defmt::{Format, debug};
+use esp_hal::{
+    Async,
+    gpio::interconnect::PeripheralOutput,
+    rmt::{PulseCode, TxChannelCreator},
+};
+use esp_hal_smartled::SmartLedsAdapterAsync;
+use smart_leds::{RGB8, SmartLedsWriteAsync};
+
+/// Gamma 2.8 lookup table
+const GAMMA: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 5, 5, 5,
+    5, 6, 6, 6, 6, 7, 7, 7, 7, 8, 8, 8, 9, 9, 9, 10, 10, 10, 11, 11, 11, 12, 12, 13, 13, 13, 14,
+    14, 15, 15, 16, 16, 17, 17, 18, 18, 19, 19, 20, 20, 21, 21, 22, 22, 23, 24, 24, 25, 25, 26, 27,
+    27, 28, 29, 29, 30, 31, 32, 32, 33, 34, 35, 35, 36, 37, 38, 39, 39, 40, 41, 42, 43, 44, 45, 46,
+    47, 48, 49, 50, 50, 51, 52, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 66, 67, 68, 69, 70, 72,
+    73, 74, 75, 77, 78, 79, 81, 82, 83, 85, 86, 87, 89, 90, 92, 93, 95, 96, 98, 99, 101, 102, 104,
+    105, 107, 109, 110, 112, 114, 115, 117, 119, 120, 122, 124, 126, 127, 129, 131, 133, 135, 137,
+    138, 140, 142, 144, 146, 148, 150, 152, 154, 156, 158, 160, 162, 164, 167, 169, 171, 173, 175,
+    177, 180, 182, 184, 186, 189, 191, 193, 196, 198, 200, 203, 205, 208, 210, 213, 215, 218, 220,
+    223, 225, 228, 231, 233, 236, 239, 241, 244, 247, 249, 252, 255,
+];
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct LedDriverConfig {
+    /// Global brightness, applied after gamma correction
+    pub brightness: u8,
+
+    /// Apply gamma correction, so that colour values are perceptually linear
+    pub gamma_correction: bool,
+
+    /// Maximum total current the LEDs may draw, colours are scaled down uniformly to stay within it
+    pub current_limit_ma: Option<u32>,
+
+    /// Current drawn by a single colour channel of a single LED at full brightness
+    pub channel_current_ma: u16,
+}
+
+impl Default for LedDriverConfig {
+    fn default() -> Self {
+        Self {
+            brightness: 255,
+            gamma_correction: true,
+            current_limit_ma: Some(500),
+            channel_current_ma: 20,
+        }
+    }
+}
+
+pub struct LedDriver<W, P> {
+    writer: W,
+    pixels: P,
+    config: LedDriverConfig,
+    dirty: bool,
+}
+
+impl<W, P> LedDriver<W, P>
+where
+    W: SmartLedsWriteAsync,
+    RGB8: Into<W::Color>,
+    P: AsRef<[RGB8]> + AsMut<[RGB8]>,
+{
+    /// Create a new driver.
+    ///
+    /// The writer may take any colour order that [`RGB8`] converts to, e.g. the GRB order of WS2812s.
+    ///
+    /// `writer` would usually be an [`esp_hal_smartled::SmartLedsAdapterAsync`], see [`LedDriver::on_rmt`] to have the
+    /// driver create it.
+    pub fn new(writer: W, pixels: P, config: LedDriverConfig) -> Self {
+        Self {
+            writer,
+            pixels,
+            config,
+            dirty: true,
+        }
+    }
+
+    /// Read only access to the pixels.
+    pub fn pixels(&self) -> &P {
+        &self.pixels
+    }
+
+    /// Mutable access to the pixels, marks the LEDs as needing to be flushed.
+    pub fn pixels_mut(&mut self) -> &mut P {
+        self.dirty = true;
+        &mut self.pixels
+    }
+
+    pub fn config(&self) -> &LedDriverConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: LedDriverConfig) {
+        self.config = config;
+        self.dirty = true;
+    }
+
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.config.brightness = brightness;
+        self.dirty = true;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Write the pixels to the LEDs, if they have changed since the last flush.
+    ///
+    /// Returns true if the LEDs were written.
+    pub async fn flush(&mut self) -> Result<bool, W::Error> {
+        if !self.dirty {
+            return Ok(false);
+        }
+
+        self.write().await?;
+        Ok(true)
+    }
+
+    /// Write the pixels to the LEDs, regardless of whether they have changed.
+    pub async fn write(&mut self) -> Result<(), W::Error> {
+        let config = self.config;
+        let pixels = self.pixels.as_ref();
+
+        // Current limiting is applied as a fraction (scale / 255) of the already corrected colour
+        let scale = match config.current_limit_ma {
+            Some(limit) => {
+                let channel_sum: u32 = pixels
+                    .iter()
+                    .map(|p| correct(*p, &config, 255))
+                    .map(|p| p.r as u32 + p.g as u32 + p.b as u32)
+                    .sum();
+                let current_ma = channel_sum * config.channel_current_ma as u32 / 255;
+
+                if current_ma > limit {
+                    debug!("LED current {} mA limited to {} mA", current_ma, limit);
+                    (limit * 255 / current_ma) as u8
+                } else {
+                    255
+                }
+            }
+            None => 255,
+        };
+
+        self.writer
+            .write(pixels.iter().map(|p| correct(*p, &config, scale)))
+            .await?;
+
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl<'d, const BUFFER_SIZE: usize, P> LedDriver<SmartLedsAdapterAsync<'d, BUFFER_SIZE>, P>
+where
+    P: AsRef<[RGB8]> + AsMut<[RGB8]> + Default,
+{
+    /// Create a new driver that owns the RMT channel driving the LEDs on `pin`, starting with all pixels off.
+    ///
+    /// `rmt_buffer` must be sized for the number of pixels, e.g.
+    /// [`FrontBoardLeds::RMT_ASYNC_BUFFER_SIZE`](crate::front::FrontBoardLeds::RMT_ASYNC_BUFFER_SIZE) for the front
+    /// board.
+    pub fn on_rmt<C>(
+        channel: C,
+        pin: impl PeripheralOutput<'d>,
+        rmt_buffer: &'d mut [PulseCode; BUFFER_SIZE],
+        config: LedDriverConfig,
+    ) -> Self
+    where
+        C: TxChannelCreator<'d, Async>,
+    {
+        let writer = SmartLedsAdapterAsync::new(channel, pin, rmt_buffer);
+        Self::new(writer, P::default(), config)
+    }
+}
+
+fn correct(pixel: RGB8, config: &LedDriverConfig, scale: u8) -> RGB8 {
+    let channel = |c: u8| {
+        let c = if config.gamma_correction {
+            GAMMA[c as usize]
+        } else {
+            c
+        };
+        let c = c as u16 * config.brightness as u16 / 255;
+        (c * scale as u16 / 255) as u8
+    };
+
+    RGB8::new(channel(pixel.r), channel(pixel.g), channel(pixel.b))
+}
@@ -0,0 +1,7 @@
+//! Physical layout of LEDs on the badge.
+//!
+//! The maths is in [`tildagon_geometry`], which has no hardware dependencies so is tested on the host.
+
+pub use tildagon_geometry::{
+    AngleNeighbours, PixelPosition, angular_distance, closest_to_angle, neighbours_of_angle,
+};
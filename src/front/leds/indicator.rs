@@ -0,0 +1,110 @@
+//! Hexpansion port status shown on the per port LEDs.
+
+use super::HexpansionPortLed;
+use crate::hexpansions::{HexpansionPort, HexpansionPortEvent, HexpansionState};
+use smart_leds::RGB8;
+use strum::{EnumCount, IntoEnumIterator};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HexpansionIndicatorColours {
+    pub disabled: RGB8,
+    pub empty: RGB8,
+    pub inserting: RGB8,
+
+    /// Occupied, and either the EEPROM has not been read yet or was read successfully
+    pub occupied: RGB8,
+
+    /// Occupied, but the EEPROM could not be found or its header was invalid
+    pub occupied_unknown_eeprom: RGB8,
+
+    pub fault: RGB8,
+}
+
+impl Default for HexpansionIndicatorColours {
+    fn default() -> Self {
+        Self {
+            disabled: RGB8::new(255, 0, 0),
+            empty: RGB8::new(255, 192, 0),
+            inserting: RGB8::new(0, 0, 255),
+            occupied: RGB8::new(255, 255, 255),
+            occupied_unknown_eeprom: RGB8::new(0, 255, 255),
+            fault: RGB8::new(255, 0, 255),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct PortIndication {
+    state: HexpansionState,
+    eeprom_valid: Option<bool>,
+}
+
+/// Tracks hexpansion port events and renders a status colour for each port.
+pub struct HexpansionPortIndicator {
+    colours: HexpansionIndicatorColours,
+    ports: [PortIndication; HexpansionPort::COUNT],
+    dirty: bool,
+}
+
+impl HexpansionPortIndicator {
+    pub fn new(colours: HexpansionIndicatorColours) -> Self {
+        Self {
+            colours,
+            ports: [PortIndication {
+                state: HexpansionState::Disabled,
+                eeprom_valid: None,
+            }; HexpansionPort::COUNT],
+            dirty: true,
+        }
+    }
+
+    pub fn handle_port_event(&mut self, event: &HexpansionPortEvent) {
+        let port = &mut self.ports[*event.port() as usize];
+        if port.state != *event.state() {
+            port.state = *event.state();
+            port.eeprom_valid = None;
+            self.dirty = true;
+        }
+    }
+
+    /// Record the result of reading the EEPROM of the hexpansion in a port.
+    ///
+    /// Ignored unless the port is occupied, and forgotten when the hexpansion is removed.
+    pub fn handle_eeprom_result(&mut self, port: HexpansionPort, valid: bool) {
+        let port = &mut self.ports[port as usize];
+        if port.state == HexpansionState::Occupied && port.eeprom_valid != Some(valid) {
+            port.eeprom_valid = Some(valid);
+            self.dirty = true;
+        }
+    }
+
+    pub fn colour(&self, port: HexpansionPort) -> RGB8 {
+        let port = &self.ports[port as usize];
+        match port.state {
+            HexpansionState::Disabled => self.colours.disabled,
+            HexpansionState::Empty => self.colours.empty,
+            HexpansionState::Inserting => self.colours.inserting,
+            HexpansionState::Occupied => match port.eeprom_valid {
+                Some(false) => self.colours.occupied_unknown_eeprom,
+                _ => self.colours.occupied,
+            },
+            HexpansionState::Fault => self.colours.fault,
+        }
+    }
+
+    /// Set the port LEDs, if anything has changed since the last render.
+    ///
+    /// Returns true if the LEDs were changed.
+    pub fn render(&mut self, buffer: &mut impl HexpansionPortLed) -> bool {
+        if !self.dirty {
+            return false;
+        }
+
+        for port in HexpansionPort::iter() {
+            *buffer.hexpansion_port(port) = self.colour(port);
+        }
+
+        self.dirty = false;
+        true
+    }
+}
@@ -1,3 +1,13 @@
+mod animation;
+mod driver;
+mod geometry;
+mod indicator;
+
+pub use animation::*;
+pub use driver::*;
+pub use geometry::*;
+pub use indicator::*;
+
 use crate::hexpansions::HexpansionPort;
 use smart_leds::RGB8;
 
@@ -23,6 +33,18 @@ impl<const N: usize> PixelBuffer<N> {
     }
 }
 
+impl<const N: usize> AsRef<[RGB8]> for PixelBuffer<N> {
+    fn as_ref(&self) -> &[RGB8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsMut<[RGB8]> for PixelBuffer<N> {
+    fn as_mut(&mut self) -> &mut [RGB8] {
+        &mut self.0
+    }
+}
+
 pub trait BaseBoardLed {
     fn base_board(&mut self) -> &mut RGB8;
 }
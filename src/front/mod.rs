@@ -1,3 +1,4 @@
+pub mod display;
 pub mod leds;
 mod variants;
 
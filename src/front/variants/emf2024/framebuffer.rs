@@ -0,0 +1,316 @@
+//! Off-screen framebuffer for the GC9A01, flushed to the display in partial updates.
+//!
+//! Drawing happens in RAM and only completed frames are sent to the panel, so there is no visible redraw flicker. Only
+//! the areas drawn to since the last flush are sent.
+//!
+//! The framebuffer is double buffered: drawing goes to the back buffer, which a flush swaps to the front before sending
+//! it, so the frame being sent is never drawn to. The new back buffer is brought up to date with the sent areas, so
+//! drawing carries on from the last frame.
+//!
+//! A full frame is 115200 bytes, with the `psram` feature enabled the second buffer can be placed in PSRAM instead of
+//! internal RAM.
+
+use super::{AsyncDisplay, AsyncDisplayError, HEIGHT, WIDTH};
+use core::convert::Infallible;
+use embedded_graphics_core::{
+    Pixel,
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Point, Size},
+    pixelcolor::{IntoStorage, Rgb565},
+    primitives::Rectangle,
+};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::{digital::Wait, spi::SpiDevice};
+use heapless::Vec;
+
+/// Size of a full frame in bytes
+pub const FRAMEBUFFER_SIZE: usize = WIDTH as usize * HEIGHT as usize * 2;
+
+/// Bytes per row of the framebuffer
+const STRIDE: usize = WIDTH as usize * 2;
+
+/// Maximum number of separate dirty areas tracked before they are merged
+const MAX_DIRTY: usize = 8;
+
+/// A framebuffer in internal RAM, the buffers would usually be allocated with `static_cell`.
+pub type StaticFramebuffer = Framebuffer<&'static mut [u8; FRAMEBUFFER_SIZE]>;
+
+#[derive(Debug, defmt::Format, Clone, Copy, PartialEq, Eq)]
+pub enum SyncedFlushError<DisplayError, TeError> {
+    Display(DisplayError),
+
+    /// Waiting for the tearing effect pulse failed
+    TearingEffect(TeError),
+}
+
+/// Double buffered framebuffer holding big endian RGB565 pixel data, i.e. exactly what is sent to the display.
+pub struct Framebuffer<B> {
+    /// Drawn to
+    back: B,
+
+    /// Most recently flushed frame
+    front: B,
+
+    dirty: Vec<Rectangle, MAX_DIRTY>,
+}
+
+impl<B> Framebuffer<B>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+{
+    /// Create a framebuffer over two buffers of at least [`FRAMEBUFFER_SIZE`] bytes each.
+    ///
+    /// The whole frame is initially marked as dirty, so that the first flush draws everything.
+    ///
+    /// # Panics
+    ///
+    /// If either buffer is too small.
+    pub fn new(back: B, front: B) -> Self {
+        assert!(back.as_ref().len() >= FRAMEBUFFER_SIZE);
+        assert!(front.as_ref().len() >= FRAMEBUFFER_SIZE);
+
+        let mut dirty = Vec::new();
+        let _ = dirty.push(Self::frame());
+
+        Self { back, front, dirty }
+    }
+
+    /// The areas that have changed since the last flush.
+    pub fn dirty(&self) -> &[Rectangle] {
+        &self.dirty
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Mark an area as needing to be sent on the next flush.
+    pub fn mark_dirty(&mut self, area: Rectangle) {
+        let mut area = area.intersection(&Self::frame());
+        if area.is_zero_sized() {
+            return;
+        }
+
+        // Absorb any areas that overlap the new one, repeating as the area grows
+        loop {
+            match self.dirty.iter().position(|d| overlaps(d, &area)) {
+                Some(i) => area = union(&area, &self.dirty.swap_remove(i)),
+                None => break,
+            }
+        }
+
+        if let Err(area) = self.dirty.push(area) {
+            // Out of space, merge with whichever existing area grows the least
+            let i = self
+                .dirty
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, d)| area_of(&union(d, &area)) - area_of(d))
+                .map(|(i, _)| i)
+                .unwrap_or_default();
+            self.dirty[i] = union(&self.dirty[i], &area);
+        }
+    }
+
+    /// Mark the whole frame as needing to be sent on the next flush.
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty.clear();
+        let _ = self.dirty.push(Self::frame());
+    }
+
+    /// Raw big endian RGB565 pixel data of the frame being drawn.
+    pub fn data(&self) -> &[u8] {
+        &self.back.as_ref()[..FRAMEBUFFER_SIZE]
+    }
+
+    /// Send the areas that have changed since the last flush to the display.
+    ///
+    /// Returns true if anything was sent.
+    pub async fn flush<SPI, DC>(
+        &mut self,
+        display: &mut AsyncDisplay<SPI, DC>,
+    ) -> Result<bool, AsyncDisplayError<SPI::Error, DC::Error>>
+    where
+        SPI: SpiDevice,
+        DC: OutputPin,
+    {
+        if self.dirty.is_empty() {
+            return Ok(false);
+        }
+
+        core::mem::swap(&mut self.back, &mut self.front);
+
+        // Bring the new back buffer up to date before sending, so that if sending fails both buffers still hold the
+        // frame and the remaining areas are sent from the next swap
+        for area in &self.dirty {
+            let x = area.top_left.x as usize * 2;
+            let width = area.size.width as usize * 2;
+            for y in area.rows() {
+                let row = y as usize * STRIDE + x..y as usize * STRIDE + x + width;
+                self.back.as_mut()[row.clone()].copy_from_slice(&self.front.as_ref()[row]);
+            }
+        }
+
+        while let Some(area) = self.dirty.last().copied() {
+            let start = area.top_left.y as usize * STRIDE + area.top_left.x as usize * 2;
+            display
+                .write_raw_strided(area, &self.front.as_ref()[start..FRAMEBUFFER_SIZE], STRIDE)
+                .await?;
+            self.dirty.pop();
+        }
+
+        Ok(true)
+    }
+
+    /// Send the areas that have changed since the last flush, starting at the next tearing effect pulse.
+    ///
+    /// This avoids the panel refreshing an area while it is being written, at the cost of waiting up to a frame period.
+    /// The EMF 2024 front board does not route the TE signal, so this is only usable with a board that does.
+    pub async fn flush_synced<SPI, DC, TE>(
+        &mut self,
+        display: &mut AsyncDisplay<SPI, DC>,
+        te: &mut TE,
+    ) -> Result<bool, SyncedFlushError<AsyncDisplayError<SPI::Error, DC::Error>, TE::Error>>
+    where
+        SPI: SpiDevice,
+        DC: OutputPin,
+        TE: Wait,
+    {
+        if self.dirty.is_empty() {
+            return Ok(false);
+        }
+
+        te.wait_for_rising_edge()
+            .await
+            .map_err(SyncedFlushError::TearingEffect)?;
+
+        self.flush(display).await.map_err(SyncedFlushError::Display)
+    }
+
+    fn frame() -> Rectangle {
+        Rectangle::new(Point::zero(), Size::new(WIDTH as u32, HEIGHT as u32))
+    }
+
+    fn set_pixel(&mut self, point: Point, colour: Rgb565) {
+        let i = point.y as usize * STRIDE + point.x as usize * 2;
+        self.back.as_mut()[i..i + 2].copy_from_slice(&colour.into_storage().to_be_bytes());
+    }
+}
+
+#[cfg(feature = "psram")]
+impl Framebuffer<&'static mut [u8]> {
+    /// Create a framebuffer with one buffer in internal RAM and the other at the start of PSRAM.
+    ///
+    /// PSRAM must have been initialised by [`esp_hal::init`] and must not be used for anything else (e.g. the heap).
+    /// Returns `None` if there is not enough PSRAM.
+    pub fn new_in_psram(
+        buffer: &'static mut [u8; FRAMEBUFFER_SIZE],
+        psram: esp_hal::peripherals::PSRAM<'static>,
+    ) -> Option<Self> {
+        let (start, len) = esp_hal::psram::psram_raw_parts(&psram);
+        if len < FRAMEBUFFER_SIZE {
+            return None;
+        }
+
+        // SAFETY: the PSRAM peripheral is consumed, so nothing else can claim this memory
+        let psram_buffer = unsafe { core::slice::from_raw_parts_mut(start, FRAMEBUFFER_SIZE) };
+        psram_buffer.fill(0);
+
+        Some(Self::new(buffer, psram_buffer))
+    }
+}
+
+/// A framebuffer with its second buffer in PSRAM.
+#[cfg(feature = "psram")]
+pub type PsramFramebuffer = Framebuffer<&'static mut [u8]>;
+
+impl<B> OriginDimensions for Framebuffer<B> {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl<B> DrawTarget for Framebuffer<B>
+where
+    B: AsRef<[u8]> + AsMut<[u8]>,
+{
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let frame = self.bounding_box();
+
+        // Track the bounds of everything drawn, rather than each individual pixel
+        let mut min = Point::new(i32::MAX, i32::MAX);
+        let mut max = Point::new(i32::MIN, i32::MIN);
+
+        for Pixel(point, colour) in pixels {
+            if frame.contains(point) {
+                self.set_pixel(point, colour);
+                min = min.component_min(point);
+                max = max.component_max(point);
+            }
+        }
+
+        if min.x <= max.x {
+            self.mark_dirty(Rectangle::with_corners(min, max));
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if area.is_zero_sized() {
+            return Ok(());
+        }
+
+        let colour = color.into_storage().to_be_bytes();
+        let x = area.top_left.x as usize * 2;
+        let width = area.size.width as usize * 2;
+
+        for y in area.rows() {
+            let start = y as usize * STRIDE + x;
+            for pixel in self.back.as_mut()[start..start + width].chunks_exact_mut(2) {
+                pixel.copy_from_slice(&colour);
+            }
+        }
+
+        self.mark_dirty(area);
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let colour = color.into_storage().to_be_bytes();
+        for pixel in self.back.as_mut()[..FRAMEBUFFER_SIZE].chunks_exact_mut(2) {
+            pixel.copy_from_slice(&colour);
+        }
+
+        self.mark_all_dirty();
+        Ok(())
+    }
+}
+
+/// True if two areas overlap or touch, in which case it is cheaper to send them as one.
+fn overlaps(a: &Rectangle, b: &Rectangle) -> bool {
+    let (a0, a1) = corners(a);
+    let (b0, b1) = corners(b);
+    a0.x <= b1.x + 1 && b0.x <= a1.x + 1 && a0.y <= b1.y + 1 && b0.y <= a1.y + 1
+}
+
+fn union(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    let (a0, a1) = corners(a);
+    let (b0, b1) = corners(b);
+    Rectangle::with_corners(a0.component_min(b0), a1.component_max(b1))
+}
+
+fn corners(r: &Rectangle) -> (Point, Point) {
+    (r.top_left, r.bottom_right().unwrap_or(r.top_left))
+}
+
+fn area_of(r: &Rectangle) -> u32 {
+    r.size.width * r.size.height
+}
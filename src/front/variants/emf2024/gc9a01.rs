@@ -1,4 +1,5 @@
-use crate::resources::FrontBoardResources;
+use crate::{eeprom::detect_eeprom_addr, resources::FrontBoardResources};
+use defmt::Format;
 use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
 use esp_hal::{
     Blocking,
@@ -6,7 +7,7 @@ use esp_hal::{
     gpio::{Level, Output},
     spi::{
         Mode,
-        master::{Config, Spi, SpiDmaBus},
+        master::{Config, ConfigError, Spi, SpiDmaBus},
     },
     time::Rate,
 };
@@ -17,6 +18,68 @@ use mipidsi::{
     options::{ColorInversion, ColorOrder, Orientation, Rotation},
 };
 
+/// Size of the receive DMA buffer, nothing is read from the display so this is as small as possible.
+pub const GC9A01_RX_DMA_BUFFER_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gc9a01Config {
+    pub rotation: Rotation,
+    pub spi_frequency: Rate,
+    pub color_order: ColorOrder,
+    pub inversion: ColorInversion,
+}
+
+impl Default for Gc9a01Config {
+    fn default() -> Self {
+        Self {
+            rotation: Rotation::Deg180,
+            spi_frequency: Rate::from_mhz(80),
+            color_order: ColorOrder::Bgr,
+            inversion: ColorInversion::Inverted,
+        }
+    }
+}
+
+impl Gc9a01Config {
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_spi_frequency(mut self, spi_frequency: Rate) -> Self {
+        self.spi_frequency = spi_frequency;
+        self
+    }
+
+    pub fn with_color_order(mut self, color_order: ColorOrder) -> Self {
+        self.color_order = color_order;
+        self
+    }
+
+    pub fn with_inversion(mut self, inversion: ColorInversion) -> Self {
+        self.inversion = inversion;
+        self
+    }
+
+    pub(crate) fn spi_config(&self) -> Config {
+        Config::default()
+            .with_frequency(self.spi_frequency)
+            .with_mode(Mode::_0)
+    }
+}
+
+/// Errors from initialising the display.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum Gc9a01Error {
+    SpiConfig(ConfigError),
+
+    /// The front board EEPROM did not respond, so there is no front board fitted
+    NoFrontBoard,
+
+    /// Communicating with the display failed
+    Interface,
+}
+
 pub struct Gc9a01 {}
 
 pub type Driver<'a> = mipidsi::Display<
@@ -32,47 +95,96 @@ pub type Driver<'a> = mipidsi::Display<
 impl Gc9a01 {
     pub type Driver<'a> = Driver<'a>;
 
-    pub fn init<
+    /// Check that a front board is fitted, then initialise the display.
+    ///
+    /// The display is write only so cannot be probed itself, instead the front board EEPROM is looked for on
+    /// `front_i2c` (a device on the front board bus).
+    ///
+    /// `dma_tx_buf` would usually be created with [`esp_hal::dma_tx_buffer!`], larger buffers need fewer transfers per
+    /// frame. Nothing is read from the display, so `dma_rx_buf` need only be [`GC9A01_RX_DMA_BUFFER_SIZE`] (e.g. from
+    /// [`esp_hal::dma_buffers!`]).
+    pub async fn init<
         'a,
         SPI: 'static + esp_hal::spi::master::Instance,
         DMA: esp_hal::dma::DmaChannel
             + esp_hal::dma::DmaChannelFor<esp_hal::spi::master::AnySpi<'static>>,
+        I2C: embedded_hal_async::i2c::I2c,
     >(
         front_board: FrontBoardResources<'static>,
+        front_i2c: &mut I2C,
         spi: SPI,
         dma: DMA,
+        dma_rx_buf: DmaRxBuf,
+        dma_tx_buf: DmaTxBuf,
         buffer: &'a mut [u8],
-    ) -> Driver<'a> {
-        #[allow(clippy::manual_div_ceil)]
-        let (rx_buffer, rx_descriptors, tx_buffer, tx_descriptors) = esp_hal::dma_buffers!(32000);
-
-        let dma_rx_buf = DmaRxBuf::new(rx_descriptors, rx_buffer).unwrap();
-        let dma_tx_buf = DmaTxBuf::new(tx_descriptors, tx_buffer).unwrap();
-
-        let spi = Spi::new(
-            spi,
-            Config::default()
-                .with_frequency(Rate::from_mhz(80))
-                .with_mode(Mode::_0),
-        )
-        .unwrap()
-        .with_sck(front_board.hs_1)
-        .with_mosi(front_board.hs_2)
-        .with_dma(dma)
-        .with_buffers(dma_rx_buf, dma_tx_buf);
+        config: Gc9a01Config,
+    ) -> Result<Driver<'a>, Gc9a01Error> {
+        probe_front_board(front_i2c).await?;
 
-        let cs = Output::new(front_board.hs_4, Level::High, Default::default());
-        let dev = ExclusiveDevice::new_no_delay(spi, cs).unwrap();
-
-        let dc = Output::new(front_board.hs_3, Level::High, Default::default());
+        let (bus, cs, dc) = spi_bus(front_board, spi, dma, dma_rx_buf, dma_tx_buf, &config)?;
+        let Ok(dev) = ExclusiveDevice::new_no_delay(bus, cs);
         let di = SpiInterface::new(dev, dc, buffer);
 
         mipidsi::Builder::new(GC9A01, di)
             .display_size(240, 240)
-            .color_order(ColorOrder::Bgr)
-            .invert_colors(ColorInversion::Inverted)
-            .orientation(Orientation::new().rotate(Rotation::Deg180))
+            .color_order(config.color_order)
+            .invert_colors(config.inversion)
+            .orientation(Orientation::new().rotate(config.rotation))
             .init(&mut embassy_time::Delay)
-            .unwrap()
+            .map_err(|_| Gc9a01Error::Interface)
+    }
+
+    /// Change the rotation of an initialised display, e.g. to follow the orientation of the badge.
+    ///
+    /// Anything already drawn is not redrawn.
+    pub fn set_rotation(display: &mut Driver<'_>, rotation: Rotation) -> Result<(), Gc9a01Error> {
+        display
+            .set_orientation(Orientation::new().rotate(rotation))
+            .map_err(|_| Gc9a01Error::Interface)
     }
 }
+
+/// Look for the front board EEPROM, as the display itself cannot be read.
+pub(crate) async fn probe_front_board<I2C>(front_i2c: &mut I2C) -> Result<(), Gc9a01Error>
+where
+    I2C: embedded_hal_async::i2c::I2c,
+{
+    detect_eeprom_addr(front_i2c)
+        .await
+        .map(|_| ())
+        .map_err(|_| Gc9a01Error::NoFrontBoard)
+}
+
+/// Set up the SPI bus to the display over DMA, along with the display's chip select and data/command pins.
+pub(crate) fn spi_bus<SPI, DMA>(
+    front_board: FrontBoardResources<'static>,
+    spi: SPI,
+    dma: DMA,
+    dma_rx_buf: DmaRxBuf,
+    dma_tx_buf: DmaTxBuf,
+    config: &Gc9a01Config,
+) -> Result<
+    (
+        SpiDmaBus<'static, Blocking>,
+        Output<'static>,
+        Output<'static>,
+    ),
+    Gc9a01Error,
+>
+where
+    SPI: 'static + esp_hal::spi::master::Instance,
+    DMA: esp_hal::dma::DmaChannel
+        + esp_hal::dma::DmaChannelFor<esp_hal::spi::master::AnySpi<'static>>,
+{
+    let bus = Spi::new(spi, config.spi_config())
+        .map_err(Gc9a01Error::SpiConfig)?
+        .with_sck(front_board.hs_1)
+        .with_mosi(front_board.hs_2)
+        .with_dma(dma)
+        .with_buffers(dma_rx_buf, dma_tx_buf);
+
+    let cs = Output::new(front_board.hs_4, Level::High, Default::default());
+    let dc = Output::new(front_board.hs_3, Level::High, Default::default());
+
+    Ok((bus, cs, dc))
+}
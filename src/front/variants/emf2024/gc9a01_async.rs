@@ -0,0 +1,465 @@
+//! Async driver for the GC9A01, so that drawing does not stall other tasks on the same executor.
+//!
+//! This does not implement [`DrawTarget`](embedded_graphics_core::draw_target::DrawTarget) (which is blocking), instead
+//! pixel data is written to an area of the display in bulk.
+
+use super::{Gc9a01, Gc9a01Config, Gc9a01Error, probe_front_board, spi_bus};
+use crate::resources::FrontBoardResources;
+use embassy_time::Timer;
+use embedded_graphics_core::{
+    pixelcolor::{IntoStorage, Rgb565},
+    primitives::Rectangle,
+};
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::spi::{Operation, SpiDevice};
+use embedded_hal_bus::spi::{ExclusiveDevice, NoDelay};
+use esp_hal::{
+    Async,
+    dma::{DmaRxBuf, DmaTxBuf},
+    gpio::Output,
+    spi::master::SpiDmaBus,
+};
+use heapless::Vec;
+use mipidsi::options::{ColorInversion, ColorOrder, Rotation};
+
+pub const WIDTH: u16 = 240;
+pub const HEIGHT: u16 = 240;
+
+mod command {
+    pub const SLEEP_IN: u8 = 0x10;
+    pub const SLEEP_OUT: u8 = 0x11;
+    pub const INVERSION_OFF: u8 = 0x20;
+    pub const INVERSION_ON: u8 = 0x21;
+    pub const DISPLAY_OFF: u8 = 0x28;
+    pub const DISPLAY_ON: u8 = 0x29;
+    pub const COLUMN_ADDRESS_SET: u8 = 0x2A;
+    pub const ROW_ADDRESS_SET: u8 = 0x2B;
+    pub const MEMORY_WRITE: u8 = 0x2C;
+    pub const TEARING_EFFECT_ON: u8 = 0x35;
+    pub const MEMORY_ACCESS_CONTROL: u8 = 0x36;
+    pub const PIXEL_FORMAT: u8 = 0x3A;
+}
+
+/// Vendor initialisation sequence, as sent by [`mipidsi`] for the GC9A01, up to setting the address mode and pixel
+/// format.
+const INIT_SEQUENCE_BEFORE_FORMAT: &[(u8, &[u8])] = &[
+    (0xEF, &[]),
+    (0xEB, &[0x14]),
+    (0xFE, &[]),
+    (0xEF, &[]),
+    (0xEB, &[0x14]),
+    (0x84, &[0x40]),
+    (0x85, &[0xFF]),
+    (0x86, &[0xFF]),
+    (0x87, &[0xFF]),
+    (0x88, &[0x0A]),
+    (0x89, &[0x21]),
+    (0x8A, &[0x00]),
+    (0x8B, &[0x80]),
+    (0x8C, &[0x01]),
+    (0x8D, &[0x01]),
+    (0x8E, &[0xFF]),
+    (0x8F, &[0xFF]),
+    (0xB6, &[0x00, 0x20]),
+];
+
+/// The rest of the vendor initialisation sequence, after setting the address mode and pixel format.
+const INIT_SEQUENCE_AFTER_FORMAT: &[(u8, &[u8])] = &[
+    (0x90, &[0x08, 0x08, 0x08, 0x08]),
+    (0xBD, &[0x06]),
+    (0xBC, &[0x00]),
+    (0xFF, &[0x60, 0x01, 0x04]),
+    (0xC3, &[0x13]),
+    (0xC4, &[0x13]),
+    (0xC9, &[0x22]),
+    (0xBE, &[0x11]),
+    (0xE1, &[0x10, 0x0E]),
+    (0xDF, &[0x20, 0x0C, 0x02]),
+    (0xF0, &[0x45, 0x09, 0x08, 0x08, 0x26, 0x2A]),
+    (0xF1, &[0x43, 0x70, 0x72, 0x36, 0x37, 0x6F]),
+    (0xF2, &[0x45, 0x09, 0x08, 0x08, 0x26, 0x2A]),
+    (0xF3, &[0x43, 0x70, 0x72, 0x36, 0x37, 0x6F]),
+    (0xED, &[0x18, 0x0B]),
+    (0xAE, &[0x77]),
+    (0xCD, &[0x63]),
+    (
+        0x70,
+        &[0x07, 0x07, 0x04, 0x0E, 0x0F, 0x09, 0x07, 0x08, 0x03],
+    ),
+    (0xE8, &[0x34]),
+    (
+        0x62,
+        &[
+            0x18, 0x0D, 0x71, 0xED, 0x70, 0x70, 0x18, 0x0F, 0x71, 0xEF, 0x70, 0x70,
+        ],
+    ),
+    (
+        0x63,
+        &[
+            0x18, 0x11, 0x71, 0xF1, 0x70, 0x70, 0x18, 0x13, 0x71, 0xF3, 0x70, 0x70,
+        ],
+    ),
+    (0x64, &[0x28, 0x29, 0xF1, 0x01, 0xF1, 0x00, 0x07]),
+    (
+        0x66,
+        &[0x3C, 0x00, 0xCD, 0x67, 0x45, 0x45, 0x10, 0x00, 0x00, 0x00],
+    ),
+    (
+        0x67,
+        &[0x00, 0x3C, 0x00, 0x00, 0x00, 0x01, 0x54, 0x10, 0x32, 0x98],
+    ),
+    (0x74, &[0x10, 0x85, 0x80, 0x00, 0x00, 0x4E, 0x00]),
+    (0x98, &[0x3E, 0x07]),
+];
+
+#[derive(Debug, defmt::Format, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncDisplayError<SpiError, PinError> {
+    Spi(SpiError),
+    Pin(PinError),
+
+    /// The area written to is outside the display
+    OutOfBounds,
+
+    /// The amount of pixel data does not match the area written to
+    DataLength,
+}
+
+pub struct AsyncDisplay<SPI, DC> {
+    spi: SPI,
+    dc: DC,
+    color_order: ColorOrder,
+}
+
+impl<SPI, DC> AsyncDisplay<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    pub fn new(spi: SPI, dc: DC) -> Self {
+        Self {
+            spi,
+            dc,
+            color_order: ColorOrder::Rgb,
+        }
+    }
+
+    /// Send the initialisation sequence and turn the display on.
+    ///
+    /// This is the same sequence as [`mipidsi`] sends, with the tearing effect output turned on in addition for
+    /// [`Framebuffer::flush_synced`](super::Framebuffer::flush_synced).
+    pub async fn init(
+        &mut self,
+        rotation: Rotation,
+        color_order: ColorOrder,
+        inversion: ColorInversion,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        // Let the panel power up
+        Timer::after_millis(200).await;
+
+        for (cmd, params) in INIT_SEQUENCE_BEFORE_FORMAT {
+            self.command(*cmd, params).await?;
+        }
+
+        self.set_orientation(rotation, color_order).await?;
+
+        // 16 bits per pixel
+        self.command(command::PIXEL_FORMAT, &[0x55]).await?;
+
+        for (cmd, params) in INIT_SEQUENCE_AFTER_FORMAT {
+            self.command(*cmd, params).await?;
+        }
+
+        self.command(
+            match inversion {
+                ColorInversion::Normal => command::INVERSION_OFF,
+                ColorInversion::Inverted => command::INVERSION_ON,
+            },
+            &[],
+        )
+        .await?;
+
+        self.command(command::TEARING_EFFECT_ON, &[0x00]).await?;
+
+        self.command(command::SLEEP_OUT, &[]).await?;
+        Timer::after_millis(120).await;
+
+        self.command(command::DISPLAY_ON, &[]).await
+    }
+
+    /// Turn showing the contents of the display memory on or off, without entering sleep.
+    pub async fn set_display_on(
+        &mut self,
+        on: bool,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.command(
+            match on {
+                true => command::DISPLAY_ON,
+                false => command::DISPLAY_OFF,
+            },
+            &[],
+        )
+        .await
+    }
+
+    /// Turn the display off and put the panel into its low power sleep mode.
+    ///
+    /// The display memory is retained, but cannot be written while asleep.
+    pub async fn sleep(&mut self) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.set_display_on(false).await?;
+        self.command(command::SLEEP_IN, &[]).await?;
+
+        // The panel must not be woken within 120 ms of entering sleep
+        Timer::after_millis(120).await;
+
+        Ok(())
+    }
+
+    /// Bring the panel out of sleep mode and turn the display back on.
+    pub async fn wake(&mut self) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.command(command::SLEEP_OUT, &[]).await?;
+        Timer::after_millis(120).await;
+
+        self.set_display_on(true).await
+    }
+
+    /// Change the rotation, keeping the current colour order.
+    ///
+    /// Anything already drawn is not redrawn.
+    pub async fn set_rotation(
+        &mut self,
+        rotation: Rotation,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.set_orientation(rotation, self.color_order).await
+    }
+
+    pub async fn set_orientation(
+        &mut self,
+        rotation: Rotation,
+        color_order: ColorOrder,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.color_order = color_order;
+
+        let mut madctl = match rotation {
+            Rotation::Deg0 => 0x00,
+            Rotation::Deg90 => 0x60,
+            Rotation::Deg180 => 0xC0,
+            Rotation::Deg270 => 0xA0,
+        };
+        if color_order == ColorOrder::Bgr {
+            madctl |= 0x08;
+        }
+
+        self.command(command::MEMORY_ACCESS_CONTROL, &[madctl])
+            .await
+    }
+
+    /// Write raw big endian RGB565 pixel data to an area of the display.
+    ///
+    /// This is the most efficient way to get pixels to the display as the data is sent over DMA as is.
+    pub async fn write_raw(
+        &mut self,
+        area: Rectangle,
+        data: &[u8],
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        if data.len() != area.size.width as usize * area.size.height as usize * 2 {
+            return Err(AsyncDisplayError::DataLength);
+        }
+
+        self.set_window(area).await?;
+        self.dc.set_high().map_err(AsyncDisplayError::Pin)?;
+        self.spi.write(data).await.map_err(AsyncDisplayError::Spi)
+    }
+
+    /// Write raw big endian RGB565 pixel data to an area of the display, taking each row from a larger buffer.
+    ///
+    /// `data` starts at the first pixel of the area and each row starts `stride` bytes after the previous one. All rows
+    /// are sent in a single SPI transaction, so the area is written as one windowed transfer.
+    pub async fn write_raw_strided(
+        &mut self,
+        area: Rectangle,
+        data: &[u8],
+        stride: usize,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        let row_len = area.size.width as usize * 2;
+        let rows = area.size.height as usize;
+
+        if rows == 0
+            || rows > HEIGHT as usize
+            || row_len > stride
+            || data.len() < (rows - 1) * stride + row_len
+        {
+            return Err(AsyncDisplayError::DataLength);
+        }
+
+        self.set_window(area).await?;
+        self.dc.set_high().map_err(AsyncDisplayError::Pin)?;
+
+        if row_len == stride {
+            // Rows are contiguous, send them in one go
+            return self
+                .spi
+                .write(&data[..rows * stride])
+                .await
+                .map_err(AsyncDisplayError::Spi);
+        }
+
+        let mut operations: Vec<Operation<'_, u8>, { HEIGHT as usize }> = data
+            .chunks(stride)
+            .take(rows)
+            .map(|row| Operation::Write(&row[..row_len]))
+            .collect();
+
+        self.spi
+            .transaction(&mut operations)
+            .await
+            .map_err(AsyncDisplayError::Spi)
+    }
+
+    /// Write pixels to an area of the display, in row major order.
+    pub async fn write_pixels(
+        &mut self,
+        area: Rectangle,
+        pixels: impl IntoIterator<Item = Rgb565>,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.set_window(area).await?;
+        self.dc.set_high().map_err(AsyncDisplayError::Pin)?;
+
+        let mut buffer = [0u8; 512];
+        let mut len = 0;
+
+        for pixel in pixels {
+            buffer[len..len + 2].copy_from_slice(&pixel.into_storage().to_be_bytes());
+            len += 2;
+
+            if len == buffer.len() {
+                self.spi
+                    .write(&buffer)
+                    .await
+                    .map_err(AsyncDisplayError::Spi)?;
+                len = 0;
+            }
+        }
+
+        if len > 0 {
+            self.spi
+                .write(&buffer[..len])
+                .await
+                .map_err(AsyncDisplayError::Spi)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fill an area of the display with a single colour.
+    pub async fn fill_solid(
+        &mut self,
+        area: Rectangle,
+        colour: Rgb565,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        let count = area.size.width as usize * area.size.height as usize;
+        self.write_pixels(area, core::iter::repeat_n(colour, count))
+            .await
+    }
+
+    /// Fill the entire display with a single colour.
+    pub async fn clear(
+        &mut self,
+        colour: Rgb565,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.fill_solid(
+            Rectangle::new(
+                embedded_graphics_core::geometry::Point::zero(),
+                embedded_graphics_core::geometry::Size::new(WIDTH as u32, HEIGHT as u32),
+            ),
+            colour,
+        )
+        .await
+    }
+
+    async fn set_window(
+        &mut self,
+        area: Rectangle,
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        let Some(bottom_right) = area.bottom_right() else {
+            return Err(AsyncDisplayError::OutOfBounds);
+        };
+        if area.top_left.x < 0
+            || area.top_left.y < 0
+            || bottom_right.x >= WIDTH as i32
+            || bottom_right.y >= HEIGHT as i32
+        {
+            return Err(AsyncDisplayError::OutOfBounds);
+        }
+
+        let x0 = (area.top_left.x as u16).to_be_bytes();
+        let x1 = (bottom_right.x as u16).to_be_bytes();
+        let y0 = (area.top_left.y as u16).to_be_bytes();
+        let y1 = (bottom_right.y as u16).to_be_bytes();
+
+        self.command(command::COLUMN_ADDRESS_SET, &[x0[0], x0[1], x1[0], x1[1]])
+            .await?;
+        self.command(command::ROW_ADDRESS_SET, &[y0[0], y0[1], y1[0], y1[1]])
+            .await?;
+        self.command(command::MEMORY_WRITE, &[]).await
+    }
+
+    async fn command(
+        &mut self,
+        cmd: u8,
+        params: &[u8],
+    ) -> Result<(), AsyncDisplayError<SPI::Error, DC::Error>> {
+        self.dc.set_low().map_err(AsyncDisplayError::Pin)?;
+        self.spi
+            .write(&[cmd])
+            .await
+            .map_err(AsyncDisplayError::Spi)?;
+
+        if !params.is_empty() {
+            self.dc.set_high().map_err(AsyncDisplayError::Pin)?;
+            self.spi
+                .write(params)
+                .await
+                .map_err(AsyncDisplayError::Spi)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub type AsyncDriver = AsyncDisplay<
+    ExclusiveDevice<SpiDmaBus<'static, Async>, Output<'static>, NoDelay>,
+    Output<'static>,
+>;
+
+impl Gc9a01 {
+    pub type AsyncDriver = AsyncDriver;
+
+    /// Check that a front board is fitted, then initialise the display, as [`Gc9a01::init`].
+    pub async fn init_async<
+        SPI: 'static + esp_hal::spi::master::Instance,
+        DMA: esp_hal::dma::DmaChannel
+            + esp_hal::dma::DmaChannelFor<esp_hal::spi::master::AnySpi<'static>>,
+        I2C: embedded_hal_async::i2c::I2c,
+    >(
+        front_board: FrontBoardResources<'static>,
+        front_i2c: &mut I2C,
+        spi: SPI,
+        dma: DMA,
+        dma_rx_buf: DmaRxBuf,
+        dma_tx_buf: DmaTxBuf,
+        config: Gc9a01Config,
+    ) -> Result<AsyncDriver, Gc9a01Error> {
+        probe_front_board(front_i2c).await?;
+
+        let (bus, cs, dc) = spi_bus(front_board, spi, dma, dma_rx_buf, dma_tx_buf, &config)?;
+        let Ok(dev) = ExclusiveDevice::new_no_delay(bus.into_async(), cs);
+
+        let mut display = AsyncDisplay::new(dev, dc);
+        display
+            .init(config.rotation, config.color_order, config.inversion)
+            .await
+            .map_err(|_| Gc9a01Error::Interface)?;
+
+        Ok(display)
+    }
+}
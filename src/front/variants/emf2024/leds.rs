@@ -1,8 +1,8 @@
-use super::Emf2024FrontBoard;
+use super::{Emf2024FrontBoard, SystemButton};
 use crate::{
     front::{
-        leds::{BaseBoardLed, FrontLeds, HexpansionPortLed},
-        variants::FrontBoardLeds,
+        leds::{BaseBoardLed, FrontLeds, HexpansionPortLed, PixelPosition},
+        variants::{FrontBoardLedGeometry, FrontBoardLeds},
     },
     hexpansions::HexpansionPort,
 };
@@ -58,3 +58,60 @@ impl FrontLeds for <Emf2024FrontBoard as FrontBoardLeds>::PixelBuffer {
         self.pixels(1..13)
     }
 }
+
+const fn position(angle: u16, radius: u8) -> PixelPosition {
+    PixelPosition { angle, radius }
+}
+
+/// Front ring LEDs are evenly spaced clockwise starting at the top, with the buttons at the corners of the hexagon
+/// (starting with A at the top) and hexpansion ports on the edges between them (starting with A clockwise of button A).
+impl FrontBoardLedGeometry for Emf2024FrontBoard {
+    type Button = SystemButton;
+
+    const POSITIONS: &'static [PixelPosition] = &[
+        position(0, 0),    // BaseBoard
+        position(0, 22),   // Front1
+        position(30, 22),  // Front2
+        position(60, 22),  // Front3
+        position(90, 22),  // Front4
+        position(120, 22), // Front5
+        position(150, 22), // Front6
+        position(180, 22), // Front7
+        position(210, 22), // Front8
+        position(240, 22), // Front9
+        position(270, 22), // Front10
+        position(300, 22), // Front11
+        position(330, 22), // Front12
+        position(30, 45),  // HexpansionA
+        position(90, 45),  // HexpansionB
+        position(150, 45), // HexpansionC
+        position(210, 45), // HexpansionD
+        position(270, 45), // HexpansionE
+        position(330, 45), // HexpansionF
+    ];
+
+    // Pixels 1 to 12
+    const FRONT_RING: &'static [PixelPosition] = Self::POSITIONS.split_at(1).1.split_at(12).0;
+
+    fn button_angle(button: SystemButton) -> u16 {
+        match button {
+            SystemButton::A => 0,
+            SystemButton::B => 60,
+            SystemButton::C => 120,
+            SystemButton::D => 180,
+            SystemButton::E => 240,
+            SystemButton::F => 300,
+        }
+    }
+
+    fn hexpansion_port_angle(port: HexpansionPort) -> u16 {
+        match port {
+            HexpansionPort::A => 30,
+            HexpansionPort::B => 90,
+            HexpansionPort::C => 150,
+            HexpansionPort::D => 210,
+            HexpansionPort::E => 270,
+            HexpansionPort::F => 330,
+        }
+    }
+}
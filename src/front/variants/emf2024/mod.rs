@@ -1,9 +1,13 @@
 mod buttons;
+mod framebuffer;
 mod gc9a01;
+mod gc9a01_async;
 mod leds;
 
 pub use buttons::*;
+pub use framebuffer::*;
 pub use gc9a01::*;
+pub use gc9a01_async::*;
 pub use leds::*;
 
 use crate::front::{
@@ -20,6 +24,7 @@ impl FrontBoardDisplay for Emf2024FrontBoard {
 impl FrontBoardLeds for Emf2024FrontBoard {
     const NUM_LEDS: usize = 19;
     const RMT_BUFFER_SIZE: usize = esp_hal_smartled::buffer_size(19);
+    const RMT_ASYNC_BUFFER_SIZE: usize = esp_hal_smartled::buffer_size_async(19);
 
     type Pixels = Pixel;
     type PixelBuffer = PixelBuffer<19>;
@@ -8,7 +8,11 @@ pub mod none;
 pub use emf2024::Emf2024FrontBoard;
 pub use none::NoFrontBoard;
 
-use crate::button_collection::ButtonCollection;
+use crate::{
+    button_collection::ButtonCollection,
+    front::leds::{AngleNeighbours, PixelPosition, closest_to_angle, neighbours_of_angle},
+    hexpansions::HexpansionPort,
+};
 
 #[derive(Debug, defmt::Format)]
 pub enum FrontBoard {
@@ -24,11 +28,47 @@ pub trait FrontBoardDisplay {
 pub trait FrontBoardLeds {
     const NUM_LEDS: usize;
     const RMT_BUFFER_SIZE: usize;
+    const RMT_ASYNC_BUFFER_SIZE: usize;
 
     type Pixels;
     type PixelBuffer;
 }
 
+/// Physical placement of the LEDs on a front board.
+///
+/// Indices returned by the provided functions are into the slice returned by
+/// [`FrontLeds::front`](crate::front::leds::FrontLeds::front).
+pub trait FrontBoardLedGeometry: FrontBoardLeds {
+    type Button;
+
+    /// Position of every pixel, indexed as in the pixel buffer
+    const POSITIONS: &'static [PixelPosition];
+
+    /// Positions of the front ring pixels, in the order they appear in the pixel buffer
+    const FRONT_RING: &'static [PixelPosition];
+
+    /// Angle of a button
+    fn button_angle(button: Self::Button) -> u16;
+
+    /// Angle of the centre of a hexpansion port
+    fn hexpansion_port_angle(port: HexpansionPort) -> u16;
+
+    /// The front ring LED closest to an angle.
+    fn front_closest_to_angle(angle: u16) -> Option<usize> {
+        closest_to_angle(Self::FRONT_RING, angle)
+    }
+
+    /// The front ring LED closest to a button.
+    fn front_closest_to_button(button: Self::Button) -> Option<usize> {
+        Self::front_closest_to_angle(Self::button_angle(button))
+    }
+
+    /// The front ring LEDs either side of a hexpansion port, and the one at it if there is one.
+    fn front_neighbours_of_hexpansion_port(port: HexpansionPort) -> Option<AngleNeighbours> {
+        neighbours_of_angle(Self::FRONT_RING, Self::hexpansion_port_angle(port))
+    }
+}
+
 pub trait FrontBoardButtons<B, I2C, const N: usize> {
     type Buttons = B;
     type ButtonCollection = ButtonCollection<B, I2C, N>;
@@ -1,7 +1,11 @@
-use crate::front::{
-    leds::{BaseBoardLed, PixelBuffer},
-    variants::FrontBoardLeds,
+use crate::{
+    front::{
+        leds::{BaseBoardLed, PixelBuffer, PixelPosition},
+        variants::{Emf2024FrontBoard, FrontBoardLedGeometry, FrontBoardLeds},
+    },
+    hexpansions::HexpansionPort,
 };
+use core::convert::Infallible;
 use defmt::Format;
 use smart_leds::RGB8;
 
@@ -10,6 +14,7 @@ pub struct NoFrontBoard;
 impl FrontBoardLeds for NoFrontBoard {
     const NUM_LEDS: usize = 1;
     const RMT_BUFFER_SIZE: usize = esp_hal_smartled::buffer_size(1);
+    const RMT_ASYNC_BUFFER_SIZE: usize = esp_hal_smartled::buffer_size_async(1);
 
     type Pixels = Pixel;
     type PixelBuffer = PixelBuffer<1>;
@@ -26,3 +31,24 @@ impl BaseBoardLed for <NoFrontBoard as FrontBoardLeds>::PixelBuffer {
         self.pixel(Pixel::BaseBoard as usize).unwrap()
     }
 }
+
+/// Only the base board LED, so there is no front ring and no buttons.
+impl FrontBoardLedGeometry for NoFrontBoard {
+    type Button = Infallible;
+
+    const POSITIONS: &'static [PixelPosition] = &[PixelPosition {
+        angle: 0,
+        radius: 0,
+    }];
+
+    const FRONT_RING: &'static [PixelPosition] = &[];
+
+    fn button_angle(button: Infallible) -> u16 {
+        match button {}
+    }
+
+    // The ports are on the base board, so are in the same place whatever the front board
+    fn hexpansion_port_angle(port: HexpansionPort) -> u16 {
+        Emf2024FrontBoard::hexpansion_port_angle(port)
+    }
+}
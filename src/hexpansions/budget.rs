@@ -0,0 +1,383 @@
+//! Power budgeting for hexpansion ports.
+//!
+//! Tracks how much current each enabled port is expected to draw, refusing to enable ports that would exceed the
+//! budget and shedding ports when the system supply browns out.
+//!
+//! The supply is measured with the BQ25895 ADC, see [`SupplyMeasurement::read`].
+
+use super::{
+    HexpansionEepromHeader, HexpansionPort, HexpansionPortControl, HexpansionPortError,
+    HexpansionPortEvent, HexpansionState,
+};
+use bq25895::{Bq25895, Interface};
+use defmt::{Format, info, warn};
+use embassy_time::{Duration, Instant, Timer};
+use getset::Getters;
+use heapless::Vec;
+use strum::EnumCount;
+
+/// The current a given hexpansion (identified by the VID/PID in its EEPROM header) is expected to draw.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct CurrentDeclaration {
+    pub vid: u16,
+    pub pid: u16,
+    pub current_ma: u16,
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct PowerBudgetConfig {
+    /// Total current available to all hexpansion ports
+    pub budget_ma: u32,
+
+    /// Current assumed for a hexpansion that has no declaration (or has not been identified yet)
+    pub default_current_ma: u16,
+
+    /// Known hexpansions and the current they draw
+    pub declarations: &'static [CurrentDeclaration],
+
+    /// System voltage below which ports are shed
+    pub brownout_vsys_mv: u16,
+
+    /// Battery charge current above which a low system voltage is not treated as a brown-out
+    ///
+    /// The charger reduces the charge current before letting the system supply sag, so while the battery is still
+    /// charging a low VSYS is due to a flat battery rather than the load.
+    pub brownout_max_charge_ma: u16,
+}
+
+impl Default for PowerBudgetConfig {
+    fn default() -> Self {
+        Self {
+            budget_ma: 1500,
+            default_current_ma: 250,
+            declarations: &[],
+            brownout_vsys_mv: 3300,
+            brownout_max_charge_ma: 100,
+        }
+    }
+}
+
+/// Measurements of the system supply, as reported by the BQ25895 ADC.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct SupplyMeasurement {
+    /// System voltage (`SYSV`, REG0F)
+    pub vsys_mv: u16,
+
+    /// Battery voltage (`BATV`, REG0E)
+    pub vbat_mv: u16,
+
+    /// Battery charge current (`ICHGR`, REG12), the charger does not measure discharge current
+    pub ibat_ma: u16,
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum SupplyMeasurementError<E> {
+    I2c(E),
+    InvalidRegisterValue,
+
+    /// The ADC conversion did not complete
+    Timeout,
+}
+
+impl<E> From<E> for SupplyMeasurementError<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+const CONVERSION_POLL: Duration = Duration::from_millis(20);
+const CONVERSION_TIMEOUT: Duration = Duration::from_millis(1000);
+
+impl SupplyMeasurement {
+    /// Take a one-shot measurement with the BQ25895 ADC.
+    pub async fn read<I2C, E>(
+        bq: &mut Bq25895<Interface<I2C>>,
+    ) -> Result<Self, SupplyMeasurementError<E>>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        bq.reg_02()
+            .modify_async(|r| r.set_conv_start(bq25895::AdcConversionControl::Started))
+            .await?;
+
+        let start = Instant::now();
+        loop {
+            Timer::after(CONVERSION_POLL).await;
+
+            let reg = bq.reg_02().read_async().await?;
+            let conv_start = reg
+                .conv_start()
+                .map_err(|_| SupplyMeasurementError::InvalidRegisterValue)?;
+            if conv_start == bq25895::AdcConversionControl::Inactive {
+                break;
+            }
+            if start.elapsed() > CONVERSION_TIMEOUT {
+                return Err(SupplyMeasurementError::Timeout);
+            }
+        }
+
+        let reg = bq.reg_0_e().read_async().await?;
+        let vbat = reg
+            .batv()
+            .map_err(|_| SupplyMeasurementError::InvalidRegisterValue)?;
+        let reg = bq.reg_0_f().read_async().await?;
+        let vsys = reg
+            .sysv()
+            .map_err(|_| SupplyMeasurementError::InvalidRegisterValue)?;
+        let reg = bq.reg_12().read_async().await?;
+        let ibat = reg
+            .ichgr()
+            .map_err(|_| SupplyMeasurementError::InvalidRegisterValue)?;
+
+        Ok(Self {
+            vbat_mv: vbat.into_inner() as u16,
+            vsys_mv: vsys.into_inner() as u16,
+            ibat_ma: ibat.into_inner() as u16,
+        })
+    }
+}
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy)]
+pub enum PowerBudgetReason {
+    /// The port was enabled with the given current allocated to it
+    Enabled { allocated_ma: u16 },
+
+    /// The port was disabled on request
+    Disabled,
+
+    /// Enabling the port was refused as it would exceed the budget
+    Refused {
+        requested_ma: u16,
+        available_ma: u32,
+    },
+
+    /// The hexpansion was identified and the current allocated to it updated
+    Declared { allocated_ma: u16 },
+
+    /// The hexpansion was identified as needing more current than is available, so the port was disabled
+    OverBudget {
+        requested_ma: u16,
+        available_ma: u32,
+    },
+
+    /// The port was disabled as the system supply browned out
+    BrownOut { vsys_mv: u16, ibat_ma: u16 },
+}
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy, Getters)]
+pub struct PowerBudgetEvent {
+    /// The port this event is about
+    #[getset(get = "pub")]
+    port: HexpansionPort,
+
+    /// The time the event happened
+    #[getset(get = "pub")]
+    time: Instant,
+
+    /// Why the port power changed
+    #[getset(get = "pub")]
+    reason: PowerBudgetReason,
+}
+
+pub struct PowerBudget {
+    config: PowerBudgetConfig,
+    /// Allocated current per enabled port, in the order the ports were enabled
+    allocations: Vec<(HexpansionPort, u16), { HexpansionPort::COUNT }>,
+}
+
+impl PowerBudget {
+    pub fn new(config: PowerBudgetConfig) -> Self {
+        Self {
+            config,
+            allocations: Vec::new(),
+        }
+    }
+
+    /// Total current currently allocated to enabled ports.
+    pub fn allocated_ma(&self) -> u32 {
+        self.allocations.iter().map(|&(_, ma)| ma as u32).sum()
+    }
+
+    /// Current that can still be allocated.
+    pub fn available_ma(&self) -> u32 {
+        self.config.budget_ma.saturating_sub(self.allocated_ma())
+    }
+
+    /// Current allocated to a port, `None` if the port is not enabled through the budget.
+    pub fn allocation(&self, port: HexpansionPort) -> Option<u16> {
+        self.allocations
+            .iter()
+            .find(|(p, _)| *p == port)
+            .map(|&(_, ma)| ma)
+    }
+
+    fn allocate(&mut self, port: HexpansionPort, current_ma: u16) {
+        match self.allocations.iter_mut().find(|(p, _)| *p == port) {
+            Some(allocation) => allocation.1 = current_ma,
+            None => {
+                let _ = self.allocations.push((port, current_ma));
+            }
+        }
+    }
+
+    fn release(&mut self, port: HexpansionPort) {
+        self.allocations.retain(|(p, _)| *p != port);
+    }
+
+    /// The current a hexpansion is expected to draw, falling back to the default if it is unknown.
+    pub fn current_for(&self, header: Option<&HexpansionEepromHeader>) -> u16 {
+        header
+            .and_then(|header| {
+                self.config
+                    .declarations
+                    .iter()
+                    .find(|d| d.vid == header.vid && d.pid == header.pid)
+            })
+            .map(|d| d.current_ma)
+            .unwrap_or(self.config.default_current_ma)
+    }
+
+    /// Enable or disable a port, if there is enough current available to do so.
+    ///
+    /// `header` can be provided if the hexpansion in the port is already known, otherwise the default current is
+    /// allocated until [`PowerBudget::declare`] is called.
+    pub async fn set_enabled<I2C, E>(
+        &mut self,
+        ports: &mut HexpansionPortControl<I2C>,
+        port: HexpansionPort,
+        enabled: bool,
+        header: Option<&HexpansionEepromHeader>,
+    ) -> Result<PowerBudgetEvent, HexpansionPortError<E>>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let reason = if enabled {
+            let requested_ma = self.current_for(header);
+            let available_ma = self.available_ma() + self.allocation(port).unwrap_or(0) as u32;
+
+            if requested_ma as u32 > available_ma {
+                PowerBudgetReason::Refused {
+                    requested_ma,
+                    available_ma,
+                }
+            } else {
+                ports.set_enabled(port, true).await?;
+                self.allocate(port, requested_ma);
+                PowerBudgetReason::Enabled {
+                    allocated_ma: requested_ma,
+                }
+            }
+        } else {
+            ports.set_enabled(port, false).await?;
+            self.release(port);
+            PowerBudgetReason::Disabled
+        };
+
+        Ok(self.event(port, reason))
+    }
+
+    /// Update the allocation for a port once the hexpansion in it has been identified.
+    ///
+    /// If the hexpansion needs more current than is available the port is disabled.
+    pub async fn declare<I2C, E>(
+        &mut self,
+        ports: &mut HexpansionPortControl<I2C>,
+        port: HexpansionPort,
+        header: &HexpansionEepromHeader,
+    ) -> Result<Option<PowerBudgetEvent>, HexpansionPortError<E>>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let Some(current_ma) = self.allocation(port) else {
+            return Ok(None);
+        };
+
+        let requested_ma = self.current_for(Some(header));
+        let available_ma = self.available_ma() + current_ma as u32;
+
+        let reason = if requested_ma as u32 > available_ma {
+            ports.set_enabled(port, false).await?;
+            self.release(port);
+            PowerBudgetReason::OverBudget {
+                requested_ma,
+                available_ma,
+            }
+        } else {
+            self.allocate(port, requested_ma);
+            PowerBudgetReason::Declared {
+                allocated_ma: requested_ma,
+            }
+        };
+
+        Ok(Some(self.event(port, reason)))
+    }
+
+    /// Keep allocations in step with the port state reported by [`HexpansionPortControl::update`].
+    ///
+    /// Ports disabled outside of the budget are released, and ports that become empty revert to the default current
+    /// as the next hexpansion inserted will need to be identified again.
+    pub fn handle_port_event(&mut self, event: &HexpansionPortEvent) {
+        match event.state() {
+            HexpansionState::Disabled | HexpansionState::Fault => self.release(*event.port()),
+            HexpansionState::Empty => {
+                if self.allocation(*event.port()).is_some() {
+                    self.allocate(*event.port(), self.config.default_current_ma);
+                }
+            }
+            HexpansionState::Inserting | HexpansionState::Occupied => {}
+        }
+    }
+
+    /// Check the system supply, shedding the most recently enabled port if it has browned out.
+    ///
+    /// A brown-out is a system voltage below [`PowerBudgetConfig::brownout_vsys_mv`] whilst the battery is not being
+    /// charged by more than [`PowerBudgetConfig::brownout_max_charge_ma`].
+    ///
+    /// Only a single port is shed per call, giving the supply chance to recover before the next measurement.
+    pub async fn check_supply<I2C, E>(
+        &mut self,
+        ports: &mut HexpansionPortControl<I2C>,
+        supply: &SupplyMeasurement,
+    ) -> Result<Option<PowerBudgetEvent>, HexpansionPortError<E>>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        if supply.vsys_mv >= self.config.brownout_vsys_mv
+            || supply.ibat_ma > self.config.brownout_max_charge_ma
+        {
+            return Ok(None);
+        }
+
+        warn!("Brown-out detected: {}", supply);
+
+        let Some(&(port, _)) = self.allocations.last() else {
+            return Ok(None);
+        };
+
+        ports.set_enabled(port, false).await?;
+        self.release(port);
+
+        Ok(Some(self.event(
+            port,
+            PowerBudgetReason::BrownOut {
+                vsys_mv: supply.vsys_mv,
+                ibat_ma: supply.ibat_ma,
+            },
+        )))
+    }
+
+    fn event(&self, port: HexpansionPort, reason: PowerBudgetReason) -> PowerBudgetEvent {
+        let event = PowerBudgetEvent {
+            port,
+            time: Instant::now(),
+            reason,
+        };
+        info!(
+            "Power budget event: {} ({}/{} mA allocated)",
+            event,
+            self.allocated_ma(),
+            self.config.budget_ma
+        );
+        event
+    }
+}
@@ -0,0 +1,91 @@
+//! Addressable LED strips carried on hexpansions.
+//!
+//! Many hexpansions have a WS2812 chain on one of their high speed pins, these wrap the same [`LedDriver`] used for
+//! the front board so that brightness and current limiting work the same way on both. The strip's pixels implement
+//! [`FrontLeds`], so effects are rendered to it with
+//! [`Animator::render_hexpansion_strip`](crate::front::leds::Animator::render_hexpansion_strip) into
+//! [`LedDriver::pixels_mut`] before flushing.
+
+use crate::{
+    front::leds::{FrontLeds, LedDriver, LedDriverConfig, PixelBuffer},
+    resources::{HexpansionHsPins, HsPin},
+};
+use esp_hal::{
+    Async,
+    rmt::{PulseCode, TxChannelCreator},
+};
+use esp_hal_smartled::SmartLedsAdapterAsync;
+use smart_leds::{RGB8, SmartLedsWriteAsync};
+
+/// Size of the RMT buffer needed to drive a strip of `n` LEDs.
+pub const fn hexpansion_led_strip_rmt_buffer_size(n: usize) -> usize {
+    esp_hal_smartled::buffer_size_async(n)
+}
+
+/// Pixels of a hexpansion LED strip.
+///
+/// The whole strip, in chain order, is the "front" LEDs.
+pub struct HexpansionStripPixels<const N: usize>(PixelBuffer<N>);
+
+impl<const N: usize> Default for HexpansionStripPixels<N> {
+    fn default() -> Self {
+        Self(PixelBuffer::default())
+    }
+}
+
+impl<const N: usize> AsRef<[RGB8]> for HexpansionStripPixels<N> {
+    fn as_ref(&self) -> &[RGB8] {
+        self.0.as_ref()
+    }
+}
+
+impl<const N: usize> AsMut<[RGB8]> for HexpansionStripPixels<N> {
+    fn as_mut(&mut self) -> &mut [RGB8] {
+        self.0.as_mut()
+    }
+}
+
+impl<const N: usize> FrontLeds for HexpansionStripPixels<N> {
+    fn front(&mut self) -> &mut [RGB8] {
+        self.0.as_mut()
+    }
+}
+
+pub type HexpansionLedStrip<W, const N: usize> = LedDriver<W, HexpansionStripPixels<N>>;
+
+/// Create a driver for an `N` LED strip on a hexpansion.
+///
+/// `writer` would usually be an [`esp_hal_smartled::SmartLedsAdapterAsync`], see [`HexpansionLedStrip::on_port`] to
+/// create one from a hexpansion port's pins.
+pub fn hexpansion_led_strip<W, const N: usize>(
+    writer: W,
+    config: LedDriverConfig,
+) -> HexpansionLedStrip<W, N>
+where
+    W: SmartLedsWriteAsync,
+    RGB8: Into<W::Color>,
+{
+    LedDriver::new(writer, HexpansionStripPixels::default(), config)
+}
+
+impl<'d, const BUFFER_SIZE: usize, const N: usize>
+    HexpansionLedStrip<SmartLedsAdapterAsync<'d, BUFFER_SIZE>, N>
+{
+    /// Create a driver for an `N` LED strip on one of the high speed pins of a hexpansion port.
+    ///
+    /// `pins` would usually be converted from the port's resources (e.g.
+    /// [`HexpansionAResources`](crate::resources::HexpansionAResources)), the pins other than `data` are released.
+    /// `rmt_buffer` must be [`hexpansion_led_strip_rmt_buffer_size`] for `N` LEDs.
+    pub fn on_port<C>(
+        channel: C,
+        pins: HexpansionHsPins<'d>,
+        data: HsPin,
+        rmt_buffer: &'d mut [PulseCode; BUFFER_SIZE],
+        config: LedDriverConfig,
+    ) -> Self
+    where
+        C: TxChannelCreator<'d, Async>,
+    {
+        LedDriver::on_rmt(channel, pins.take(data), rmt_buffer, config)
+    }
+}
@@ -0,0 +1,366 @@
+//! Register level control of the five low speed (LS) pins of a hexpansion port.
+//!
+//! The LS pins are AW9523 pins, which [`embedded_aw9523`] only offers one at a time and with no way back from a
+//! conversion. This tracks the mode of each pin so misuse is caught, and can update all the digital outputs of a port
+//! in a single write per expander (port B is split over two expanders, the others are on one).
+//!
+//! Inputs can be waited on without polling with an [`LsWaiter`], fed by an
+//! [`LsInterruptDispatcher`](super::LsInterruptDispatcher). Reading a pin directly clears the expander interrupt, so
+//! waiters do not read.
+//!
+//! Registers are read, modified and written while holding [`EXPANDER_LOCK`], other code changing expander registers
+//! must hold it too. The badge's own outputs sharing registers with LS pins are
+//! [`LockedOutput`](crate::pins::LockedOutput)s, which do.
+
+use super::{HexpansionPort, LsInterrupts, LsWaiter};
+use crate::pins::{EXPANDER_LOCK, ExpanderPin, HexpansionLsPins};
+use defmt::{Format, debug};
+use embassy_sync::blocking_mutex::raw::RawMutex;
+use embedded_aw9523::Input;
+use heapless::Vec;
+use strum::{EnumCount, EnumIter, IntoEnumIterator};
+
+/// First of the output, output, config, config registers
+const OUTPUT_REGISTER: u8 = 0x02;
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy, EnumIter, EnumCount)]
+pub enum LsPin {
+    Ls1,
+    Ls2,
+    Ls3,
+    Ls4,
+    Ls5,
+}
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy)]
+pub enum LsMode {
+    Input,
+
+    /// Push-pull output (port 0 pins are open drain unless the expander is configured otherwise)
+    Output,
+
+    /// Driven low, or released (high impedance) to be pulled high externally
+    OpenDrain,
+
+    /// Constant current sink with 256 levels, for driving LEDs directly
+    Led,
+}
+
+#[derive(Debug, Format)]
+pub enum LowSpeedIoError<E> {
+    I2c(E),
+
+    /// The operation needs the pin in a different mode
+    WrongMode {
+        pin: LsPin,
+        mode: LsMode,
+    },
+
+    /// The pin is already in another non-input mode, return it to [`LsMode::Input`] first
+    ModeConflict {
+        pin: LsPin,
+        mode: LsMode,
+    },
+}
+
+impl<E> From<E> for LowSpeedIoError<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy)]
+struct PinState {
+    mode: LsMode,
+
+    /// Level for outputs, released for open drain, brightness for LEDs
+    level: u8,
+}
+
+pub struct LowSpeedIo<I2C> {
+    i2c: I2C,
+    port: HexpansionPort,
+    pins: [ExpanderPin; LsPin::COUNT],
+    state: [PinState; LsPin::COUNT],
+    claims: [Input<I2C>; LsPin::COUNT],
+    interrupt_enabled: [bool; LsPin::COUNT],
+}
+
+impl<I2C, E> LowSpeedIo<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// Take the LS pins of a port, which are all left as inputs.
+    ///
+    /// `i2c` is a device on the system bus used for register access.
+    pub fn new<P: HexpansionLsPins<I2C>>(i2c: I2C, pins: P) -> Self {
+        Self {
+            i2c,
+            port: P::PORT,
+            pins: pins.inputs().map(ExpanderPin::of),
+            state: [PinState {
+                mode: LsMode::Input,
+                level: 0,
+            }; LsPin::COUNT],
+            claims: pins.into_inputs(),
+            interrupt_enabled: [false; LsPin::COUNT],
+        }
+    }
+
+    pub fn port(&self) -> HexpansionPort {
+        self.port
+    }
+
+    pub fn mode(&self, pin: LsPin) -> LsMode {
+        self.state[pin as usize].mode
+    }
+
+    /// Location of a pin, e.g. for matching against input register reads.
+    pub fn expander_pin(&self, pin: LsPin) -> ExpanderPin {
+        self.pins[pin as usize]
+    }
+
+    pub async fn into_input(&mut self, pin: LsPin) -> Result<(), LowSpeedIoError<E>> {
+        self.set_mode(pin, LsMode::Input, 0).await
+    }
+
+    pub async fn into_output(&mut self, pin: LsPin, high: bool) -> Result<(), LowSpeedIoError<E>> {
+        self.set_mode(pin, LsMode::Output, high as u8).await
+    }
+
+    /// Make the pin open drain, starting released.
+    pub async fn into_open_drain(&mut self, pin: LsPin) -> Result<(), LowSpeedIoError<E>> {
+        self.set_mode(pin, LsMode::OpenDrain, 1).await
+    }
+
+    /// Put the pin in LED drive mode, starting off.
+    pub async fn into_led(&mut self, pin: LsPin) -> Result<(), LowSpeedIoError<E>> {
+        self.set_mode(pin, LsMode::Led, 0).await
+    }
+
+    /// Read a pin, in input or open drain mode.
+    pub async fn is_high(&mut self, pin: LsPin) -> Result<bool, LowSpeedIoError<E>> {
+        self.check_mode(pin, &[LsMode::Input, LsMode::OpenDrain])?;
+
+        let location = self.pins[pin as usize];
+        let mut value = [0];
+        self.i2c
+            .write_read(location.address, &[location.input_register()], &mut value)
+            .await?;
+        Ok(value[0] & location.mask() != 0)
+    }
+
+    /// Read all pins, `None` for those not in input or open drain mode.
+    pub async fn read_all(&mut self) -> Result<[Option<bool>; LsPin::COUNT], LowSpeedIoError<E>> {
+        let mut levels = [None; LsPin::COUNT];
+        let mut last: Option<(u8, [u8; 2])> = None;
+
+        for (i, location) in self.pins.iter().enumerate() {
+            if !matches!(self.state[i].mode, LsMode::Input | LsMode::OpenDrain) {
+                continue;
+            }
+
+            // Both input registers are read together, and only once per expander
+            let registers = match last {
+                Some((address, registers)) if address == location.address => registers,
+                _ => {
+                    let mut registers = [0u8; 2];
+                    self.i2c
+                        .write_read(location.address, &[0x00], &mut registers)
+                        .await?;
+                    last = Some((location.address, registers));
+                    registers
+                }
+            };
+
+            levels[i] = Some(registers[location.port as usize] & location.mask() != 0);
+        }
+
+        Ok(levels)
+    }
+
+    /// Enable the interrupt of a pin, in input or open drain mode, and return a waiter for its changes.
+    ///
+    /// The interrupt stays enabled until the pins are released, changing the pin to another mode does not stop the
+    /// waiter.
+    pub async fn waiter<'a, M: RawMutex>(
+        &mut self,
+        pin: LsPin,
+        interrupts: &'a LsInterrupts<M>,
+    ) -> Result<LsWaiter<'a, M>, LowSpeedIoError<E>> {
+        self.check_mode(pin, &[LsMode::Input, LsMode::OpenDrain])?;
+
+        if !self.interrupt_enabled[pin as usize] {
+            let location = self.pins[pin as usize];
+            location
+                .modify(&mut self.i2c, location.interrupt_register(), false)
+                .await?;
+            self.interrupt_enabled[pin as usize] = true;
+            interrupts.forget_level(self.port, pin);
+        }
+
+        Ok(LsWaiter::new(interrupts, self.port, pin))
+    }
+
+    /// Set the level of an output, or drive/release an open drain pin.
+    pub async fn set_level(&mut self, pin: LsPin, high: bool) -> Result<(), LowSpeedIoError<E>> {
+        let mut levels = [None; LsPin::COUNT];
+        levels[pin as usize] = Some(high);
+        self.set_levels(levels).await
+    }
+
+    /// Set several output and open drain pins at once, `None` leaves a pin unchanged.
+    ///
+    /// Fails without changing anything if any pin given a level is not an output or open drain.
+    pub async fn set_levels(
+        &mut self,
+        levels: [Option<bool>; LsPin::COUNT],
+    ) -> Result<(), LowSpeedIoError<E>> {
+        for (pin, level) in LsPin::iter().zip(levels) {
+            if level.is_some() {
+                self.check_mode(pin, &[LsMode::Output, LsMode::OpenDrain])?;
+            }
+        }
+
+        for (state, level) in self.state.iter_mut().zip(levels) {
+            if let Some(level) = level {
+                state.level = level as u8;
+            }
+        }
+
+        self.write_digital().await
+    }
+
+    /// Set the current of a pin in LED mode, 0 is off.
+    pub async fn set_brightness(
+        &mut self,
+        pin: LsPin,
+        brightness: u8,
+    ) -> Result<(), LowSpeedIoError<E>> {
+        self.check_mode(pin, &[LsMode::Led])?;
+        self.state[pin as usize].level = brightness;
+
+        let location = self.pins[pin as usize];
+        self.i2c
+            .write(location.address, &[location.dim_register(), brightness])
+            .await?;
+        Ok(())
+    }
+
+    /// Release the pins, returning them as inputs with their interrupts disabled.
+    pub async fn release(mut self) -> Result<[Input<I2C>; LsPin::COUNT], LowSpeedIoError<E>> {
+        for pin in LsPin::iter() {
+            self.into_input(pin).await?;
+
+            if self.interrupt_enabled[pin as usize] {
+                let location = self.pins[pin as usize];
+                location
+                    .modify(&mut self.i2c, location.interrupt_register(), true)
+                    .await?;
+                self.interrupt_enabled[pin as usize] = false;
+            }
+        }
+        Ok(self.claims)
+    }
+
+    fn check_mode(&self, pin: LsPin, allowed: &[LsMode]) -> Result<(), LowSpeedIoError<E>> {
+        let mode = self.state[pin as usize].mode;
+        match allowed.contains(&mode) {
+            true => Ok(()),
+            false => Err(LowSpeedIoError::WrongMode { pin, mode }),
+        }
+    }
+
+    async fn set_mode(
+        &mut self,
+        pin: LsPin,
+        mode: LsMode,
+        level: u8,
+    ) -> Result<(), LowSpeedIoError<E>> {
+        let current = self.state[pin as usize].mode;
+        if current == mode {
+            return Ok(());
+        }
+        if current != LsMode::Input && mode != LsMode::Input {
+            return Err(LowSpeedIoError::ModeConflict { pin, mode: current });
+        }
+
+        debug!("Hexpansion {} {}: {} -> {}", self.port, pin, current, mode);
+
+        let location = self.pins[pin as usize];
+
+        // Leave LED mode first so the pin is never sinking current as something else
+        if current == LsMode::Led {
+            location
+                .modify(&mut self.i2c, location.led_mode_register(), true)
+                .await?;
+        }
+
+        if mode == LsMode::Led {
+            self.i2c
+                .write(location.address, &[location.dim_register(), 0])
+                .await?;
+        }
+
+        self.state[pin as usize] = PinState { mode, level };
+        self.write_digital().await?;
+
+        if mode == LsMode::Led {
+            location
+                .modify(&mut self.i2c, location.led_mode_register(), false)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the output and direction of every pin, once per expander.
+    async fn write_digital(&mut self) -> Result<(), LowSpeedIoError<E>> {
+        let mut addresses: Vec<u8, { LsPin::COUNT }> = Vec::new();
+        for location in self.pins {
+            if !addresses.contains(&location.address) {
+                let _ = addresses.push(location.address);
+            }
+        }
+
+        for address in addresses {
+            let _lock = EXPANDER_LOCK.lock().await;
+
+            let mut registers = [0u8; 4];
+            self.i2c
+                .write_read(address, &[OUTPUT_REGISTER], &mut registers)
+                .await?;
+
+            for (location, state) in self.pins.iter().zip(self.state) {
+                if location.address != address {
+                    continue;
+                }
+
+                let (output, input) = match state.mode {
+                    LsMode::Input => (false, true),
+                    LsMode::Output => (state.level != 0, false),
+                    LsMode::OpenDrain => (false, state.level != 0),
+                    LsMode::Led => (false, false),
+                };
+
+                let port = location.port as usize;
+                set_bit(&mut registers[port], location.mask(), output);
+                set_bit(&mut registers[2 + port], location.mask(), input);
+            }
+
+            let mut write = [0u8; 5];
+            write[0] = OUTPUT_REGISTER;
+            write[1..].copy_from_slice(&registers);
+            self.i2c.write(address, &write).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn set_bit(value: &mut u8, mask: u8, set: bool) {
+    match set {
+        true => *value |= mask,
+        false => *value &= !mask,
+    }
+}
@@ -0,0 +1,217 @@
+//! Fan out of system interrupts to tasks waiting on changes of hexpansion LS pins.
+//!
+//! The AW9523 expanders raise the shared system interrupt when an input (with its interrupt enabled) changes. Whatever
+//! task waits on [`SystemInterrupt`](crate::system_interrupt::SystemInterrupt) reads the input registers (which also
+//! clears the interrupt) with [`PinControl::read_system_bus_input_registers`](crate::pins::PinControl) and passes them
+//! to [`LsInterruptDispatcher::dispatch`] alongside its other handling. That wakes the [`LsWaiter`] of any LS pin that
+//! changed, see [`LowSpeedIo::waiter`](super::LowSpeedIo::waiter).
+//!
+//! Only the levels seen at each dispatch are compared, so a pulse that is over before the input registers are read is
+//! not seen.
+
+use super::{HexpansionPort, LsPin};
+use crate::pins::{ExpanderPin, HexpansionLsPins, Pins};
+use core::cell::Cell;
+use defmt::{Format, debug};
+use embassy_sync::{
+    blocking_mutex::{Mutex, raw::RawMutex},
+    signal::Signal,
+};
+use embedded_aw9523::{InputRegisters, InputRegistersError};
+use embedded_hal::digital::PinState;
+use strum::{EnumCount, IntoEnumIterator};
+
+type Levels = [[Option<bool>; LsPin::COUNT]; HexpansionPort::COUNT];
+
+/// Changes of a pin since its waiter last looked.
+#[derive(Debug, Format, Default, PartialEq, Eq, Clone, Copy)]
+struct LsEdges {
+    rising: bool,
+    falling: bool,
+
+    /// Level at the most recent dispatch
+    level: bool,
+}
+
+/// Per pin notifications and levels, shared between the dispatcher and waiters so would usually be in a `static`.
+pub struct LsInterrupts<M: RawMutex> {
+    signals: [[Signal<M, LsEdges>; LsPin::COUNT]; HexpansionPort::COUNT],
+
+    /// Level of each pin at the most recent dispatch, `None` if not known
+    levels: Mutex<M, Cell<Levels>>,
+}
+
+impl<M: RawMutex> LsInterrupts<M> {
+    pub const fn new() -> Self {
+        Self {
+            signals: [const { [const { Signal::new() }; LsPin::COUNT] }; HexpansionPort::COUNT],
+            levels: Mutex::new(Cell::new([[None; LsPin::COUNT]; HexpansionPort::COUNT])),
+        }
+    }
+
+    fn signal(&self, port: HexpansionPort, pin: LsPin) -> &Signal<M, LsEdges> {
+        &self.signals[port as usize][pin as usize]
+    }
+
+    fn level(&self, port: HexpansionPort, pin: LsPin) -> Option<bool> {
+        self.levels
+            .lock(|levels| levels.get()[port as usize][pin as usize])
+    }
+
+    /// Set the level of a pin, returning the previous one.
+    fn set_level(&self, port: HexpansionPort, pin: LsPin, level: Option<bool>) -> Option<bool> {
+        self.levels.lock(|levels| {
+            let mut all = levels.get();
+            let previous = all[port as usize][pin as usize];
+            all[port as usize][pin as usize] = level;
+            levels.set(all);
+            previous
+        })
+    }
+
+    /// Forget the level of a pin whose interrupt has just been enabled, as it may have changed unseen since the last
+    /// dispatch.
+    pub(crate) fn forget_level(&self, port: HexpansionPort, pin: LsPin) {
+        self.set_level(port, pin, None);
+    }
+}
+
+impl<M: RawMutex> Default for LsInterrupts<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct LsInterruptDispatcher {
+    pins: [[ExpanderPin; LsPin::COUNT]; HexpansionPort::COUNT],
+}
+
+impl LsInterruptDispatcher {
+    /// `pins` are only used to find where the LS pins are, so this must be created before they are handed out.
+    pub fn new<SysI2C>(pins: &Pins<SysI2C>) -> Self {
+        Self {
+            pins: [
+                pins.hexpansion_a.inputs().map(ExpanderPin::of),
+                pins.hexpansion_b.inputs().map(ExpanderPin::of),
+                pins.hexpansion_c.inputs().map(ExpanderPin::of),
+                pins.hexpansion_d.inputs().map(ExpanderPin::of),
+                pins.hexpansion_e.inputs().map(ExpanderPin::of),
+                pins.hexpansion_f.inputs().map(ExpanderPin::of),
+            ],
+        }
+    }
+
+    /// Notify the waiters of the LS pins that changed.
+    ///
+    /// Call this with the input registers read each time the system interrupt fires. No registers are read here, so
+    /// the interrupt is only cleared by that one read. The first dispatch only records the levels.
+    pub fn dispatch<M: RawMutex>(
+        &self,
+        regs: &InputRegisters,
+        interrupts: &LsInterrupts<M>,
+    ) -> Result<(), InputRegistersError> {
+        for port in HexpansionPort::iter() {
+            for (pin, location) in LsPin::iter().zip(&self.pins[port as usize]) {
+                let level = regs.pin_state(location)? == PinState::High;
+
+                // The level is updated before signalling, so a waiter checking it first cannot miss the change
+                let previous = interrupts.set_level(port, pin, Some(level));
+                if previous == Some(level) {
+                    continue;
+                }
+
+                // Merge with anything not yet seen by the waiter, so that quick successive edges are not lost
+                let signal = interrupts.signal(port, pin);
+                let mut edges = signal.try_take().unwrap_or_default();
+                if previous.is_some() {
+                    debug!("Hexpansion {} {} changed: {}", port, pin, level);
+                    edges.rising |= level;
+                    edges.falling |= !level;
+                }
+                edges.level = level;
+                signal.signal(edges);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Waits on changes of one LS pin, created with [`LowSpeedIo::waiter`](super::LowSpeedIo::waiter).
+///
+/// Waiting only needs a shared reference and does no I2C access, so the [`LowSpeedIo`](super::LowSpeedIo) the pin
+/// belongs to can still be used meanwhile. Only one task should wait on a pin at a time.
+pub struct LsWaiter<'a, M: RawMutex> {
+    interrupts: &'a LsInterrupts<M>,
+    port: HexpansionPort,
+    pin: LsPin,
+}
+
+impl<'a, M: RawMutex> LsWaiter<'a, M> {
+    pub(crate) fn new(interrupts: &'a LsInterrupts<M>, port: HexpansionPort, pin: LsPin) -> Self {
+        Self {
+            interrupts,
+            port,
+            pin,
+        }
+    }
+
+    pub fn port(&self) -> HexpansionPort {
+        self.port
+    }
+
+    pub fn pin(&self) -> LsPin {
+        self.pin
+    }
+
+    /// Wait for the pin to be high.
+    ///
+    /// Returns immediately if it was at the last dispatch. The level of a new waiter's pin is not known until the next
+    /// dispatch.
+    pub async fn wait_for_high(&self) {
+        self.wait_for_level(true).await
+    }
+
+    /// Wait for the pin to be low.
+    ///
+    /// Returns immediately if it was at the last dispatch. The level of a new waiter's pin is not known until the next
+    /// dispatch.
+    pub async fn wait_for_low(&self) {
+        self.wait_for_level(false).await
+    }
+
+    /// Wait for the pin to change, returning the new level.
+    ///
+    /// Only changes dispatched after the call are seen.
+    pub async fn wait_for_any_edge(&self) -> bool {
+        let signal = self.interrupts.signal(self.port, self.pin);
+        signal.reset();
+        loop {
+            let edges = signal.wait().await;
+            if edges.rising || edges.falling {
+                return edges.level;
+            }
+        }
+    }
+
+    async fn wait_for_level(&self, high: bool) {
+        // Cleared before checking so that a change after the check is not missed
+        let signal = self.interrupts.signal(self.port, self.pin);
+        signal.reset();
+        if self.interrupts.level(self.port, self.pin) == Some(high) {
+            return;
+        }
+
+        loop {
+            let edges = signal.wait().await;
+            // A pulse to the wanted level counts, even if it has already ended
+            let reached = match high {
+                true => edges.rising,
+                false => edges.falling,
+            };
+            if reached || edges.level == high {
+                return;
+            }
+        }
+    }
+}
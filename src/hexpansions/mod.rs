@@ -1,5 +1,13 @@
+mod budget;
 mod eeprom;
+mod leds;
+mod low_speed;
+mod ls_interrupt;
 mod ports;
 
+pub use budget::*;
 pub use eeprom::*;
+pub use leds::*;
+pub use low_speed::*;
+pub use ls_interrupt::*;
 pub use ports::*;
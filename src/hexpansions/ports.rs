@@ -1,9 +1,7 @@
-use crate::pins::HexpansionDetectPins;
-use defmt::{Format, debug};
-use embassy_time::Instant;
-use embedded_aw9523::{
-    Input, InputRegisters, Output, PinConfiguration, async_traits::digital::OutputPin,
-};
+use crate::pins::{ExpanderPin, HexpansionDetectPins};
+use defmt::{Format, debug, warn};
+use embassy_time::{Duration, Instant};
+use embedded_aw9523::{Input, InputRegisters, InputRegistersError};
 use embedded_hal::digital::PinState;
 use getset::Getters;
 use heapless::{Vec, index_map::FnvIndexMap};
@@ -19,104 +17,226 @@ pub enum HexpansionPort {
     F,
 }
 
+#[derive(Debug, Format)]
+pub enum HexpansionPortError<E> {
+    I2c(E),
+    InputRegisters(InputRegistersError),
+
+    /// The port has no state, should never happen
+    MissingState(HexpansionPort),
+}
+
+impl<E> From<E> for HexpansionPortError<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+/// Debouncing applied to the hexpansion detect pins.
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy)]
+pub struct DetectDebounce {
+    /// How long the detect pin must be held low before a hexpansion is considered to be inserting
+    pub insertion: Duration,
+
+    /// How long the detect pin must be released before a hexpansion is considered removed
+    pub removal: Duration,
+
+    /// How long a hexpansion must remain inserted before it is considered stable and powered
+    pub settling: Duration,
+}
+
+impl Default for DetectDebounce {
+    fn default() -> Self {
+        Self {
+            insertion: Duration::from_millis(50),
+            removal: Duration::from_millis(100),
+            settling: Duration::from_millis(250),
+        }
+    }
+}
+
 pub struct HexpansionPortControl<I2C> {
+    i2c: I2C,
     state: FnvIndexMap<HexpansionPort, PortState<I2C>, 8>,
+    debounce: DetectDebounce,
 }
 
 impl<I2C, E> HexpansionPortControl<I2C>
 where
     I2C: embedded_hal_async::i2c::I2c<Error = E>,
 {
-    pub async fn new(pins: HexpansionDetectPins<I2C>) -> Result<Self, E> {
-        let mut state = FnvIndexMap::new();
-
-        let _ = state.insert(
-            HexpansionPort::A,
-            PortState {
-                mode: PortModeState::new(pins.a).await?,
-                notified: false,
-            },
-        );
-        let _ = state.insert(
-            HexpansionPort::B,
-            PortState {
-                mode: PortModeState::new(pins.b).await?,
-                notified: false,
-            },
-        );
-        let _ = state.insert(
-            HexpansionPort::C,
-            PortState {
-                mode: PortModeState::new(pins.c).await?,
-                notified: false,
-            },
-        );
-        let _ = state.insert(
-            HexpansionPort::D,
-            PortState {
-                mode: PortModeState::new(pins.d).await?,
-                notified: false,
-            },
-        );
-        let _ = state.insert(
-            HexpansionPort::E,
-            PortState {
-                mode: PortModeState::new(pins.e).await?,
-                notified: false,
-            },
-        );
-        let _ = state.insert(
-            HexpansionPort::F,
-            PortState {
-                mode: PortModeState::new(pins.f).await?,
-                notified: false,
-            },
-        );
+    /// Take control of the ports, which are all disabled.
+    ///
+    /// `i2c` is a device on the system bus, used to switch the detect pins between driving the port disabled and
+    /// sensing insertion.
+    pub async fn new(
+        i2c: I2C,
+        pins: HexpansionDetectPins<I2C>,
+    ) -> Result<Self, HexpansionPortError<E>> {
+        let mut control = Self {
+            i2c,
+            state: FnvIndexMap::new(),
+            debounce: DetectDebounce::default(),
+        };
 
-        Ok(Self { state })
+        let pins = [pins.a, pins.b, pins.c, pins.d, pins.e, pins.f];
+        for (port, pin) in HexpansionPort::iter().zip(pins) {
+            let _ = control.state.insert(
+                port,
+                PortState {
+                    location: ExpanderPin::of(&pin),
+                    pin,
+                    mode: PortMode::Faulted { enabled: false },
+                    notified: false,
+                },
+            );
+            control.set_enabled(port, false).await?;
+        }
+
+        Ok(control)
     }
 
-    pub async fn set_enabled(&mut self, port: HexpansionPort, enabled: bool) -> Result<(), E> {
-        let mut state = self.state.remove(&port).unwrap();
-        state.mode = if enabled {
-            state.mode.enable().await?
-        } else {
-            state.mode.disable().await?
+    pub fn with_debounce(mut self, debounce: DetectDebounce) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Enable or disable power to a port.
+    ///
+    /// Enabling a port that is already enabled does nothing, so its detection state (and anything tracking it, such as
+    /// the power budget) is kept. Should the change fail the port is left in [`HexpansionState::Fault`], calling this
+    /// again retries it.
+    pub async fn set_enabled(
+        &mut self,
+        port: HexpansionPort,
+        enabled: bool,
+    ) -> Result<(), HexpansionPortError<E>> {
+        let state = self
+            .state
+            .get_mut(&port)
+            .ok_or(HexpansionPortError::MissingState(port))?;
+
+        if enabled && matches!(state.mode, PortMode::Enabled { .. }) {
+            return Ok(());
+        }
+
+        if let PortMode::Faulted { enabled: previous } = state.mode {
+            debug!(
+                "Hexpansion port {} retrying after failing to set enabled to {}",
+                port, previous
+            );
+        }
+
+        let location = state.location;
+        let result = match enabled {
+            true => {
+                location
+                    .modify(&mut self.i2c, location.config_register(), true)
+                    .await
+            }
+            // Latch the output high before switching direction so the port is never briefly driven on
+            false => match location
+                .modify(&mut self.i2c, location.output_register(), true)
+                .await
+            {
+                Ok(()) => {
+                    location
+                        .modify(&mut self.i2c, location.config_register(), false)
+                        .await
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        state.mode = match result {
+            Ok(()) if enabled => PortMode::Enabled {
+                detect: DetectState::Empty { asserted_at: None },
+            },
+            Ok(()) => PortMode::Disabled,
+            Err(_) => {
+                warn!("Hexpansion port {} transition failed", port);
+                PortMode::Faulted { enabled }
+            }
         };
         state.notified = false;
-        let _ = self.state.insert(port, state);
-        Ok(())
+
+        result.map_err(HexpansionPortError::I2c)
+    }
+
+    /// Enable or disable power to several ports.
+    ///
+    /// All ports are attempted, the first error encountered is returned.
+    pub async fn set_enabled_many(
+        &mut self,
+        ports: impl IntoIterator<Item = HexpansionPort>,
+        enabled: bool,
+    ) -> Result<(), HexpansionPortError<E>> {
+        let mut result = Ok(());
+        for port in ports {
+            let r = self.set_enabled(port, enabled).await;
+            if result.is_ok() {
+                result = r;
+            }
+        }
+        result
     }
 
+    pub async fn enable_all(&mut self) -> Result<(), HexpansionPortError<E>> {
+        self.set_enabled_many(HexpansionPort::iter(), true).await
+    }
+
+    pub async fn disable_all(&mut self) -> Result<(), HexpansionPortError<E>> {
+        self.set_enabled_many(HexpansionPort::iter(), false).await
+    }
+
+    /// The current state of a port.
+    pub fn state(&self, port: HexpansionPort) -> Option<HexpansionState> {
+        self.state.get(&port).map(|state| state.state())
+    }
+
+    /// The current state of all ports.
+    pub fn iter(&self) -> impl Iterator<Item = (HexpansionPort, HexpansionState)> + '_ {
+        HexpansionPort::iter().filter_map(|port| self.state(port).map(|state| (port, state)))
+    }
+
+    /// Emit an event for every port on the next call to [`HexpansionPortControl::update`], regardless of whether it
+    /// has changed.
+    ///
+    /// Useful for subscribers that start after the initial events have been published.
+    pub fn force_notify(&mut self) {
+        for state in self.state.values_mut() {
+            state.notified = false;
+        }
+    }
+
+    /// Update port states from the current detect pin states.
+    ///
+    /// This should be called periodically, not just when the system interrupt fires, so that debounce and settling
+    /// times can elapse.
     pub fn update(
         &mut self,
         regs: &InputRegisters,
-    ) -> Vec<HexpansionPortEvent, { HexpansionPort::COUNT }> {
+    ) -> Result<Vec<HexpansionPortEvent, { HexpansionPort::COUNT }>, HexpansionPortError<E>> {
         let now = Instant::now();
 
         let mut events = Vec::new();
 
         for hex_port in HexpansionPort::iter() {
-            let port_state = self.state.get_mut(&hex_port).unwrap();
+            let port_state = self
+                .state
+                .get_mut(&hex_port)
+                .ok_or(HexpansionPortError::MissingState(hex_port))?;
 
-            if let PortModeState::Enabled {
-                ref pin,
-                ref mut present,
-            } = port_state.mode
-            {
-                match regs.pin_state(pin).unwrap() {
-                    PinState::Low => {
-                        if !*present {
-                            port_state.notified = false;
-                        }
-                        *present = true;
-                    }
-                    PinState::High => {
-                        if *present {
-                            port_state.notified = false;
-                        }
-                        *present = false;
-                    }
+            if let PortMode::Enabled { ref mut detect } = port_state.mode {
+                let asserted = regs
+                    .pin_state(&port_state.pin)
+                    .map_err(HexpansionPortError::InputRegisters)?
+                    == PinState::Low;
+
+                let previous = detect.state();
+                *detect = detect.next(asserted, now, &self.debounce);
+                if detect.state() != previous {
+                    port_state.notified = false;
                 }
             }
 
@@ -131,63 +251,114 @@ where
         }
 
         debug!("Hexpansion events: {}", events);
-        events
+        Ok(events)
     }
 }
 
 struct PortState<I2C> {
-    mode: PortModeState<I2C>,
+    /// Held for the lifetime of the controller, its direction is changed through [`ExpanderPin`] register access
+    pin: Input<I2C>,
+    location: ExpanderPin,
+    mode: PortMode,
     notified: bool,
 }
 
 impl<I2C> PortState<I2C> {
     fn state(&self) -> HexpansionState {
         match &self.mode {
-            PortModeState::Disabled { pin: _ } => HexpansionState::Disabled,
-            PortModeState::Enabled { pin: _, present } => {
-                if *present {
-                    HexpansionState::Occupied
-                } else {
-                    HexpansionState::Empty
-                }
-            }
+            PortMode::Disabled => HexpansionState::Disabled,
+            PortMode::Enabled { detect } => detect.state(),
+            PortMode::Faulted { .. } => HexpansionState::Fault,
         }
     }
 }
 
-enum PortModeState<I2C> {
-    Disabled { pin: Output<I2C> },
-    Enabled { pin: Input<I2C>, present: bool },
+enum PortMode {
+    Disabled,
+    Enabled {
+        detect: DetectState,
+    },
+
+    /// Changing to the given state failed part way, the pin may be left in either state
+    Faulted {
+        enabled: bool,
+    },
 }
 
-impl<I2C, E> PortModeState<I2C>
-where
-    I2C: embedded_hal_async::i2c::I2c<Error = E>,
-{
-    async fn new<PIN: PinConfiguration<I2C, E>>(pin: PIN) -> Result<Self, E> {
-        let mut pin = pin.try_into_output().await?;
-        pin.set_high().await.unwrap(); // TODO
-        Ok(Self::Disabled { pin })
-    }
+#[derive(Clone, Copy)]
+enum DetectState {
+    /// No hexpansion, the detect pin may have been asserted at the given time but not for long enough to count yet
+    Empty { asserted_at: Option<Instant> },
 
-    async fn disable(self) -> Result<Self, E> {
-        let mut pin = match self {
-            PortModeState::Disabled { pin } => pin.try_into_output().await?,
-            PortModeState::Enabled { pin, present: _ } => pin.try_into_output().await?,
-        };
-        pin.set_high().await.unwrap(); // TODO
-        Ok(Self::Disabled { pin })
+    /// A hexpansion has been inserted and is settling, the detect pin may have been released at the given time
+    Inserting {
+        since: Instant,
+        released_at: Option<Instant>,
+    },
+
+    /// A hexpansion is present and stable, the detect pin may have been released at the given time
+    Occupied { released_at: Option<Instant> },
+}
+
+impl DetectState {
+    fn state(&self) -> HexpansionState {
+        match self {
+            DetectState::Empty { .. } => HexpansionState::Empty,
+            DetectState::Inserting { .. } => HexpansionState::Inserting,
+            DetectState::Occupied { .. } => HexpansionState::Occupied,
+        }
     }
 
-    async fn enable(self) -> Result<Self, E> {
-        let pin = match self {
-            PortModeState::Disabled { pin } => pin.try_into_input().await?,
-            PortModeState::Enabled { pin, present: _ } => pin.try_into_input().await?,
-        };
-        Ok(Self::Enabled {
-            pin,
-            present: false,
-        })
+    fn next(self, asserted: bool, now: Instant, debounce: &DetectDebounce) -> Self {
+        match (self, asserted) {
+            (DetectState::Empty { asserted_at }, true) => {
+                let asserted_at = asserted_at.unwrap_or(now);
+                if now - asserted_at >= debounce.insertion {
+                    DetectState::Inserting {
+                        since: now,
+                        released_at: None,
+                    }
+                } else {
+                    DetectState::Empty {
+                        asserted_at: Some(asserted_at),
+                    }
+                }
+            }
+            (DetectState::Empty { .. }, false) => DetectState::Empty { asserted_at: None },
+            (DetectState::Inserting { since, .. }, true) => {
+                if now - since >= debounce.settling {
+                    DetectState::Occupied { released_at: None }
+                } else {
+                    DetectState::Inserting {
+                        since,
+                        released_at: None,
+                    }
+                }
+            }
+            (DetectState::Inserting { released_at, .. }, false) => {
+                let released_at = released_at.unwrap_or(now);
+                if now - released_at >= debounce.removal {
+                    DetectState::Empty { asserted_at: None }
+                } else {
+                    // A glitch whilst settling restarts the settling time
+                    DetectState::Inserting {
+                        since: now,
+                        released_at: Some(released_at),
+                    }
+                }
+            }
+            (DetectState::Occupied { .. }, true) => DetectState::Occupied { released_at: None },
+            (DetectState::Occupied { released_at }, false) => {
+                let released_at = released_at.unwrap_or(now);
+                if now - released_at >= debounce.removal {
+                    DetectState::Empty { asserted_at: None }
+                } else {
+                    DetectState::Occupied {
+                        released_at: Some(released_at),
+                    }
+                }
+            }
+        }
     }
 }
 
@@ -214,6 +385,13 @@ pub enum HexpansionState {
     /// The port is enabled, but no hexpansion is demanding power from it
     Empty,
 
+    /// The port is enabled and a hexpansion has just been inserted, it is not yet stable
+    Inserting,
+
     /// The port is enabled and contains a hexpansion that is demanding power
     Occupied,
+
+    /// The last change to the port power failed and it is in an unknown electrical state, until it is retried with
+    /// [`HexpansionPortControl::set_enabled`]
+    Fault,
 }
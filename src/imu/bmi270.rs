@@ -0,0 +1,376 @@
+//! Async register level driver for the BMI270.
+//!
+//! Unlike [`bmi2`] this never blocks the executor, the config file upload in particular is split into chunks with the
+//! bus released and other tasks given a chance to run between each one.
+
+use defmt::{Format, debug, info};
+use embassy_futures::yield_now;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+/// Address of the BMI270 on the system I2C bus (SDO pulled high)
+pub const BMI270_ADDRESS: u8 = 0x69;
+
+pub const BMI270_CHIP_ID: u8 = 0x24;
+
+/// Bytes of the config file written per I2C transaction, must be even
+const CONFIG_CHUNK_SIZE: usize = 128;
+
+/// Size of each page of the feature engine configuration
+const FEATURE_PAGE_SIZE: usize = 16;
+
+/// Largest register write done in one transaction
+const WRITE_CHUNK_SIZE: usize = 16;
+
+pub(crate) mod register {
+    pub const CHIP_ID: u8 = 0x00;
+    pub const ERR_REG: u8 = 0x02;
+    pub const DATA_ACC_X: u8 = 0x0C;
+    pub const INT_STATUS_0: u8 = 0x1C;
+    pub const INTERNAL_STATUS: u8 = 0x21;
+    pub const FIFO_LENGTH_0: u8 = 0x24;
+    pub const FIFO_DATA: u8 = 0x26;
+    pub const FEAT_PAGE: u8 = 0x2F;
+    pub const FEATURES: u8 = 0x30;
+    pub const ACC_CONF: u8 = 0x40;
+    pub const ACC_RANGE: u8 = 0x41;
+    pub const GYR_CONF: u8 = 0x42;
+    pub const GYR_RANGE: u8 = 0x43;
+    pub const FIFO_DOWNS: u8 = 0x45;
+    pub const FIFO_WTM_0: u8 = 0x46;
+    pub const FIFO_CONFIG_0: u8 = 0x48;
+    pub const FIFO_CONFIG_1: u8 = 0x49;
+    pub const INT1_IO_CTRL: u8 = 0x53;
+    pub const INT_LATCH: u8 = 0x55;
+    pub const INT1_MAP_FEAT: u8 = 0x56;
+    pub const INT_MAP_DATA: u8 = 0x58;
+    pub const INIT_CTRL: u8 = 0x59;
+    pub const INIT_ADDR_0: u8 = 0x5B;
+    pub const INIT_DATA: u8 = 0x5E;
+    pub const NV_CONF: u8 = 0x70;
+    pub const OFFSET_0: u8 = 0x71;
+    pub const PWR_CONF: u8 = 0x7C;
+    pub const PWR_CTRL: u8 = 0x7D;
+    pub const CMD: u8 = 0x7E;
+}
+
+const CMD_SOFT_RESET: u8 = 0xB6;
+const CMD_FIFO_FLUSH: u8 = 0xB0;
+
+/// Size of a headerless FIFO frame containing both accelerometer and gyroscope data
+pub const FIFO_FRAME_SIZE: usize = 12;
+
+#[derive(Debug, Format)]
+pub enum Bmi270Error<E> {
+    I2c(E),
+
+    /// The device at the IMU address is not a BMI270
+    ChipId(u8),
+
+    /// The config file was not accepted, contains the last value of `INTERNAL_STATUS`
+    Init(u8),
+}
+
+impl<E> From<E> for Bmi270Error<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OutputDataRate {
+    Hz12_5 = 0x05,
+    Hz25 = 0x06,
+    Hz50 = 0x07,
+    Hz100 = 0x08,
+    Hz200 = 0x09,
+    Hz400 = 0x0A,
+    Hz800 = 0x0B,
+    Hz1600 = 0x0C,
+}
+
+impl OutputDataRate {
+    /// Time between samples.
+    pub fn period(&self) -> Duration {
+        Duration::from_micros(80_000 >> (*self as u8 - Self::Hz12_5 as u8))
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccelRange {
+    G2 = 0x00,
+    G4 = 0x01,
+    G8 = 0x02,
+    G16 = 0x03,
+}
+
+impl AccelRange {
+    /// Raw reading corresponding to 1 g.
+    pub fn lsb_per_g(&self) -> f32 {
+        (16384 >> (*self as u8)) as f32
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum GyroRange {
+    Dps2000 = 0x00,
+    Dps1000 = 0x01,
+    Dps500 = 0x02,
+    Dps250 = 0x03,
+    Dps125 = 0x04,
+}
+
+impl GyroRange {
+    /// Raw reading corresponding to 1 degree per second.
+    pub fn lsb_per_dps(&self) -> f32 {
+        16.384 * (1 << (*self as u8)) as f32
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct SensorConfig {
+    pub accel_odr: OutputDataRate,
+    pub accel_range: AccelRange,
+    pub gyro_odr: OutputDataRate,
+    pub gyro_range: GyroRange,
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            accel_odr: OutputDataRate::Hz100,
+            accel_range: AccelRange::G4,
+            gyro_odr: OutputDataRate::Hz100,
+            gyro_range: GyroRange::Dps2000,
+        }
+    }
+}
+
+/// Raw readings from a single sample.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RawSensorData {
+    pub acc: [i16; 3],
+    pub gyr: [i16; 3],
+}
+
+impl RawSensorData {
+    /// Parse data in register order, accelerometer X LSB first.
+    pub(crate) fn from_le_bytes(data: &[u8; 12]) -> Self {
+        let axis = |i: usize| i16::from_le_bytes([data[i * 2], data[i * 2 + 1]]);
+        Self {
+            acc: [axis(0), axis(1), axis(2)],
+            gyr: [axis(3), axis(4), axis(5)],
+        }
+    }
+}
+
+pub struct Bmi270<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C, E> Bmi270<I2C>
+where
+    I2C: I2c<Error = E>,
+{
+    pub fn new(i2c: I2C) -> Self {
+        Self::new_with_address(i2c, BMI270_ADDRESS)
+    }
+
+    pub fn new_with_address(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// The bus the IMU is on, e.g. for reaching the expander its interrupt is wired to.
+    pub(crate) fn i2c(&mut self) -> &mut I2C {
+        &mut self.i2c
+    }
+
+    pub async fn chip_id(&mut self) -> Result<u8, E> {
+        self.read_register(register::CHIP_ID).await
+    }
+
+    /// Read the error register, non-zero indicates a fatal or configuration error.
+    pub async fn error(&mut self) -> Result<u8, E> {
+        self.read_register(register::ERR_REG).await
+    }
+
+    pub async fn soft_reset(&mut self) -> Result<(), E> {
+        self.write_register(register::CMD, CMD_SOFT_RESET).await?;
+        Timer::after_millis(2).await;
+        Ok(())
+    }
+
+    /// Reset the device and upload its config file.
+    ///
+    /// The config file would usually be [`bmi2::config::BMI270_CONFIG_FILE`].
+    pub async fn init(&mut self, config_file: &[u8]) -> Result<(), Bmi270Error<E>> {
+        let chip_id = self.chip_id().await?;
+        info!("IMU chip ID: {}", chip_id);
+        if chip_id != BMI270_CHIP_ID {
+            return Err(Bmi270Error::ChipId(chip_id));
+        }
+
+        self.soft_reset().await?;
+
+        // Advanced power save must be disabled for the upload
+        self.write_register(register::PWR_CONF, 0x00).await?;
+        Timer::after_micros(450).await;
+
+        self.write_register(register::INIT_CTRL, 0x00).await?;
+        self.upload_config(config_file).await?;
+        self.write_register(register::INIT_CTRL, 0x01).await?;
+
+        // Initialisation takes up to 20 ms
+        let deadline = Instant::now() + Duration::from_millis(20);
+        loop {
+            Timer::after_millis(1).await;
+
+            let status = self.read_register(register::INTERNAL_STATUS).await?;
+            if status & 0x0F == 0x01 {
+                info!("IMU initialised");
+                return Ok(());
+            }
+            if Instant::now() > deadline {
+                return Err(Bmi270Error::Init(status));
+            }
+        }
+    }
+
+    async fn upload_config(&mut self, config_file: &[u8]) -> Result<(), E> {
+        let mut buffer = [0u8; CONFIG_CHUNK_SIZE + 1];
+        buffer[0] = register::INIT_DATA;
+
+        for (i, chunk) in config_file.chunks(CONFIG_CHUNK_SIZE).enumerate() {
+            // The address is in words, split as 4 low bits then 8 high bits
+            let word = (i * CONFIG_CHUNK_SIZE / 2) as u16;
+            self.i2c
+                .write(
+                    self.address,
+                    &[
+                        register::INIT_ADDR_0,
+                        (word & 0x0F) as u8,
+                        (word >> 4) as u8,
+                    ],
+                )
+                .await?;
+
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c
+                .write(self.address, &buffer[..=chunk.len()])
+                .await?;
+
+            yield_now().await;
+        }
+
+        debug!("Uploaded {} byte IMU config", config_file.len());
+        Ok(())
+    }
+
+    /// Turn the accelerometer, gyroscope and temperature sensor on or off.
+    pub async fn enable_sensors(&mut self, acc: bool, gyr: bool, temp: bool) -> Result<(), E> {
+        self.write_register(
+            register::PWR_CTRL,
+            ((gyr as u8) << 1) | ((acc as u8) << 2) | ((temp as u8) << 3),
+        )
+        .await
+    }
+
+    /// Set the output data rate and range of the accelerometer and gyroscope.
+    ///
+    /// Both use the default (normal) filter and performance mode.
+    pub async fn configure(&mut self, config: &SensorConfig) -> Result<(), E> {
+        // Performance optimised filtering, normal averaging
+        self.write_register(register::ACC_CONF, 0xA0 | config.accel_odr as u8)
+            .await?;
+        self.write_register(register::ACC_RANGE, config.accel_range as u8)
+            .await?;
+        self.write_register(register::GYR_CONF, 0xA0 | config.gyro_odr as u8)
+            .await?;
+        self.write_register(register::GYR_RANGE, config.gyro_range as u8)
+            .await
+    }
+
+    /// Number of bytes waiting in the FIFO.
+    pub async fn fifo_length(&mut self) -> Result<u16, E> {
+        let mut length = [0u8; 2];
+        self.read_registers(register::FIFO_LENGTH_0, &mut length)
+            .await?;
+        Ok(u16::from_le_bytes(length) & 0x3FFF)
+    }
+
+    /// Read bytes from the FIFO, this should not be more than [`Bmi270::fifo_length`].
+    pub async fn read_fifo(&mut self, data: &mut [u8]) -> Result<(), E> {
+        self.read_registers(register::FIFO_DATA, data).await
+    }
+
+    pub async fn flush_fifo(&mut self) -> Result<(), E> {
+        self.write_register(register::CMD, CMD_FIFO_FLUSH).await
+    }
+
+    /// Read the most recent accelerometer and gyroscope sample.
+    pub async fn read_sensor_data(&mut self) -> Result<RawSensorData, E> {
+        let mut data = [0u8; 12];
+        self.read_registers(register::DATA_ACC_X, &mut data).await?;
+        Ok(RawSensorData::from_le_bytes(&data))
+    }
+
+    /// Read from a page of the feature engine configuration, starting at a byte offset within the page.
+    ///
+    /// Panics if the read would run past the end of the page.
+    pub async fn read_feature(&mut self, page: u8, offset: u8, data: &mut [u8]) -> Result<(), E> {
+        check_feature_range(offset, data.len());
+        self.write_register(register::FEAT_PAGE, page).await?;
+        self.read_registers(register::FEATURES + offset, data).await
+    }
+
+    /// Write to a page of the feature engine configuration, starting at a byte offset within the page.
+    ///
+    /// Panics if the write would run past the end of the page, which would write the registers that follow it.
+    pub async fn write_feature(&mut self, page: u8, offset: u8, data: &[u8]) -> Result<(), E> {
+        check_feature_range(offset, data.len());
+        self.write_register(register::FEAT_PAGE, page).await?;
+        self.write_registers(register::FEATURES + offset, data)
+            .await
+    }
+
+    pub async fn read_register(&mut self, register: u8) -> Result<u8, E> {
+        let mut value = [0];
+        self.read_registers(register, &mut value).await?;
+        Ok(value[0])
+    }
+
+    pub async fn read_registers(&mut self, register: u8, data: &mut [u8]) -> Result<(), E> {
+        self.i2c.write_read(self.address, &[register], data).await
+    }
+
+    pub async fn write_register(&mut self, register: u8, value: u8) -> Result<(), E> {
+        self.i2c.write(self.address, &[register, value]).await
+    }
+
+    /// Write consecutive registers, in transactions of up to 16 bytes with the register address advanced for each.
+    pub async fn write_registers(&mut self, register: u8, data: &[u8]) -> Result<(), E> {
+        let mut buffer = [0u8; WRITE_CHUNK_SIZE + 1];
+        for (i, chunk) in data.chunks(WRITE_CHUNK_SIZE).enumerate() {
+            buffer[0] = register + (i * WRITE_CHUNK_SIZE) as u8;
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c
+                .write(self.address, &buffer[..=chunk.len()])
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// A feature page is mapped to the 16 registers from [`register::FEATURES`].
+fn check_feature_range(offset: u8, len: usize) {
+    assert!(
+        offset as usize + len <= FEATURE_PAGE_SIZE,
+        "feature access of {} bytes at offset {} is outside the page",
+        len,
+        offset
+    );
+}
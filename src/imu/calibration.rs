@@ -0,0 +1,313 @@
+//! Per badge offset calibration of the IMU.
+//!
+//! Offsets are measured with the badge held still in a known orientation (the same approach as the fast offset
+//! compensation in Bosch's driver), applied using the BMI270's offset registers so that all readings, including those
+//! used by the feature engine, are corrected. This is the only bias correction, nothing downstream (e.g.
+//! [`OrientationEstimator`](super::OrientationEstimator)) applies its own.
+//!
+//! Calibrations are saved in a [`sequential_storage`] map on any async [`NorFlash`], on the badge this is the `nvs`
+//! partition of the ESP32-S3 flash (see [`ImuCalibrationStore::in_flash`]) which [`init`](super::init) loads them from.
+
+use super::{Bmi270, DiscreteOrientation, SensorConfig, register};
+use core::ops::Range;
+use defmt::{Format, debug, info, warn};
+use embassy_embedded_hal::adapter::BlockingAsync;
+use embassy_time::Timer;
+use embedded_storage_async::nor_flash::NorFlash;
+use esp_bootloader_esp_idf::partitions::{
+    self, DataPartitionSubType, PARTITION_TABLE_MAX_LEN, PartitionType,
+};
+use esp_hal::peripherals::FLASH;
+use esp_storage::{FlashStorage, FlashStorageError};
+use micromath::F32Ext;
+use sequential_storage::{
+    cache::{Cache, Uncached},
+    map::{MapConfig, MapConfigError, MapStorage, SerializationError, Value},
+};
+use tildagon_fusion::StationaryDetector;
+
+/// Size of a stored calibration record.
+pub const IMU_CALIBRATION_RECORD_SIZE: usize = 10;
+
+const RECORD_VERSION: u8 = 2;
+
+/// Key the calibration record is stored under
+const CALIBRATION_KEY: u8 = 0;
+
+/// Space for the key and record of an item, rounded up to the flash word size
+const ITEM_BUFFER_SIZE: usize = (1 + IMU_CALIBRATION_RECORD_SIZE).next_multiple_of(4);
+
+/// Accelerometer offset register resolution, in g
+const ACCEL_OFFSET_LSB_G: f32 = 0.0039;
+
+/// Gyroscope offset register resolution, in degrees per second
+const GYRO_OFFSET_LSB_DPS: f32 = 0.061;
+
+/// Largest deviation of gyroscope readings from the first, in degrees per second, for the badge to be considered still
+const MAX_GYRO_DEVIATION_DPS: f32 = 5.0;
+
+/// Offsets in the units of the BMI270 offset registers.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImuCalibration {
+    /// Accelerometer offset in 3.9 mg steps
+    pub accel_offset: [i8; 3],
+
+    /// Gyroscope offset in 0.061 degree per second steps, limited to 10 bits
+    pub gyro_offset: [i16; 3],
+}
+
+impl ImuCalibration {
+    pub fn to_bytes(&self) -> [u8; IMU_CALIBRATION_RECORD_SIZE] {
+        let mut record = [0u8; IMU_CALIBRATION_RECORD_SIZE];
+        record[0] = RECORD_VERSION;
+        for (i, offset) in self.accel_offset.iter().enumerate() {
+            record[1 + i] = *offset as u8;
+        }
+        for (i, offset) in self.gyro_offset.iter().enumerate() {
+            record[4 + i * 2..6 + i * 2].copy_from_slice(&offset.to_le_bytes());
+        }
+        record
+    }
+
+    /// Parse a stored record, returns `None` if it was written by an incompatible version.
+    ///
+    /// Records are not checksummed, the map they are stored in checks the integrity of its items.
+    pub fn from_bytes(record: &[u8; IMU_CALIBRATION_RECORD_SIZE]) -> Option<Self> {
+        if record[0] != RECORD_VERSION {
+            return None;
+        }
+
+        let gyro = |i: usize| i16::from_le_bytes([record[4 + i * 2], record[5 + i * 2]]);
+        Some(Self {
+            accel_offset: [record[1] as i8, record[2] as i8, record[3] as i8],
+            gyro_offset: [gyro(0), gyro(1), gyro(2)],
+        })
+    }
+}
+
+impl<'a> Value<'a> for ImuCalibration {
+    fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, SerializationError> {
+        buffer
+            .get_mut(..IMU_CALIBRATION_RECORD_SIZE)
+            .ok_or(SerializationError::BufferTooSmall)?
+            .copy_from_slice(&self.to_bytes());
+        Ok(IMU_CALIBRATION_RECORD_SIZE)
+    }
+
+    fn deserialize_from(buffer: &'a [u8]) -> Result<(Self, usize), SerializationError> {
+        let record = buffer
+            .get(..IMU_CALIBRATION_RECORD_SIZE)
+            .ok_or(SerializationError::BufferTooSmall)?
+            .try_into()
+            .unwrap();
+        let calibration = Self::from_bytes(record).ok_or(SerializationError::InvalidFormat)?;
+        Ok((calibration, IMU_CALIBRATION_RECORD_SIZE))
+    }
+}
+
+#[derive(Debug, Format)]
+pub enum ImuCalibrationError<E> {
+    I2c(E),
+
+    /// The badge was moved during calibration
+    Moved,
+}
+
+impl<E> From<E> for ImuCalibrationError<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+impl<I2C, E> Bmi270<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// Load offsets into the offset registers and enable compensation.
+    pub async fn set_calibration(&mut self, calibration: &ImuCalibration) -> Result<(), E> {
+        let mut offsets = [0u8; 7];
+        for (i, offset) in calibration.accel_offset.iter().enumerate() {
+            offsets[i] = *offset as u8;
+        }
+
+        // Low 8 bits of each gyroscope offset, then the top 2 bits of each packed into the last register
+        let gain = self.read_register(register::OFFSET_0 + 6).await? & 0x80;
+        offsets[6] = gain | 0x40;
+        for (i, offset) in calibration.gyro_offset.iter().enumerate() {
+            let offset = (*offset).clamp(-512, 511);
+            offsets[3 + i] = offset as u8;
+            offsets[6] |= (((offset >> 8) as u8) & 0x03) << (i * 2);
+        }
+
+        self.write_registers(register::OFFSET_0, &offsets).await?;
+
+        let nv_conf = self.read_register(register::NV_CONF).await?;
+        self.write_register(register::NV_CONF, nv_conf | 0x08).await
+    }
+
+    /// Disable offset compensation, readings are then uncorrected.
+    pub async fn clear_calibration(&mut self) -> Result<(), E> {
+        let nv_conf = self.read_register(register::NV_CONF).await?;
+        self.write_register(register::NV_CONF, nv_conf & !0x08)
+            .await?;
+
+        let offset_6 = self.read_register(register::OFFSET_0 + 6).await?;
+        self.write_register(register::OFFSET_0 + 6, offset_6 & !0x40)
+            .await
+    }
+
+    /// Measure the offsets of both sensors, and apply them.
+    ///
+    /// The badge must be held still with `up` facing upwards (usually [`DiscreteOrientation::FaceUp`], lying on a
+    /// table) for the duration, which is `samples` periods of the accelerometer data rate. Both sensors must be
+    /// enabled and configured with `config`.
+    pub async fn calibrate(
+        &mut self,
+        config: &SensorConfig,
+        up: DiscreteOrientation,
+        samples: u16,
+    ) -> Result<ImuCalibration, ImuCalibrationError<E>> {
+        self.clear_calibration().await?;
+
+        let lsb_per_g = config.accel_range.lsb_per_g();
+        let lsb_per_dps = config.gyro_range.lsb_per_dps();
+        let period = config.accel_odr.period().max(config.gyro_odr.period());
+        let samples = samples.max(1);
+
+        // Let the sensors settle after the offsets are cleared
+        Timer::after(period * 2).await;
+
+        let mut still = StationaryDetector::new((MAX_GYRO_DEVIATION_DPS * lsb_per_dps) as i32);
+        let mut accel_sum = [0i32; 3];
+
+        for _ in 0..samples {
+            Timer::after(period).await;
+            let data = self.read_sensor_data().await?;
+
+            still
+                .push(data.gyr)
+                .map_err(|_| ImuCalibrationError::Moved)?;
+            for (sum, value) in accel_sum.iter_mut().zip(data.acc) {
+                *sum += value as i32;
+            }
+        }
+
+        let accel_mean = accel_sum.map(|s| s as f32 / samples as f32);
+        let gyro_mean = still.mean().unwrap_or_default();
+        let expected = up.up();
+
+        let mut calibration = ImuCalibration::default();
+        for i in 0..3 {
+            let accel_error = accel_mean[i] / lsb_per_g - expected[i];
+            calibration.accel_offset[i] = (-accel_error / ACCEL_OFFSET_LSB_G)
+                .round()
+                .clamp(-128.0, 127.0) as i8;
+
+            let gyro_error = gyro_mean[i] as f32 / lsb_per_dps;
+            calibration.gyro_offset[i] = (-gyro_error / GYRO_OFFSET_LSB_DPS)
+                .round()
+                .clamp(-512.0, 511.0) as i16;
+        }
+
+        debug!("IMU means: {} {}", accel_mean, gyro_mean);
+        info!("IMU calibration: {}", calibration);
+
+        self.set_calibration(&calibration).await?;
+        Ok(calibration)
+    }
+}
+
+#[derive(Debug, Format)]
+pub enum ImuCalibrationStoreError<E> {
+    /// The partition table could not be read
+    PartitionTable(partitions::Error),
+
+    /// The partition table has no `nvs` partition
+    NoPartition,
+
+    /// The reserved flash is not usable for a map, e.g. too small
+    Config(MapConfigError),
+
+    Storage(sequential_storage::Error<E>),
+}
+
+impl<E> From<sequential_storage::Error<E>> for ImuCalibrationStoreError<E> {
+    fn from(e: sequential_storage::Error<E>) -> Self {
+        Self::Storage(e)
+    }
+}
+
+/// Stores the calibration in a key-value map on a reserved region of flash.
+pub struct ImuCalibrationStore<S: NorFlash> {
+    map: MapStorage<u8, S, Cache<Uncached, Uncached, Uncached, u8>>,
+}
+
+impl<S> ImuCalibrationStore<S>
+where
+    S: NorFlash,
+{
+    /// `range` is the flash reserved for the store, it must be aligned to and at least two of the erase size of
+    /// `storage`.
+    pub fn new(storage: S, range: Range<u32>) -> Result<Self, ImuCalibrationStoreError<S::Error>> {
+        let config = MapConfig::try_new(range).map_err(ImuCalibrationStoreError::Config)?;
+        Ok(Self {
+            map: MapStorage::new(storage, config, Cache::new_uncached()),
+        })
+    }
+
+    pub fn into_inner(self) -> S {
+        self.map.destroy().0
+    }
+
+    /// Read the stored calibration, `None` if nothing has been saved.
+    pub async fn load(
+        &mut self,
+    ) -> Result<Option<ImuCalibration>, ImuCalibrationStoreError<S::Error>> {
+        let mut buffer = [0u8; ITEM_BUFFER_SIZE];
+        Ok(self.map.fetch_item(&mut buffer, &CALIBRATION_KEY).await?)
+    }
+
+    /// Save the calibration, replacing any previously saved.
+    ///
+    /// If the reserved flash holds something other than a map (e.g. left by other firmware) it is erased first.
+    pub async fn save(
+        &mut self,
+        calibration: &ImuCalibration,
+    ) -> Result<(), ImuCalibrationStoreError<S::Error>> {
+        let mut buffer = [0u8; ITEM_BUFFER_SIZE];
+        match self
+            .map
+            .store_item(&mut buffer, &CALIBRATION_KEY, calibration)
+            .await
+        {
+            Err(sequential_storage::Error::Corrupted { .. }) => {
+                warn!("IMU calibration store corrupted, erasing");
+                self.map.erase_all().await?;
+                Ok(self
+                    .map
+                    .store_item(&mut buffer, &CALIBRATION_KEY, calibration)
+                    .await?)
+            }
+            result => Ok(result?),
+        }
+    }
+}
+
+impl<'d> ImuCalibrationStore<BlockingAsync<FlashStorage<'d>>> {
+    /// Store the calibration in the `nvs` data partition of the ESP32-S3 flash, found from the partition table.
+    ///
+    /// The partition is used as a [`sequential_storage`] map rather than in the ESP-IDF NVS format, nothing else in the
+    /// badge firmware uses it.
+    pub fn in_flash(flash: FLASH<'d>) -> Result<Self, ImuCalibrationStoreError<FlashStorageError>> {
+        let mut storage = FlashStorage::new(flash);
+
+        let mut table = [0u8; PARTITION_TABLE_MAX_LEN];
+        let partition = partitions::read_partition_table(&mut storage, &mut table)
+            .and_then(|table| table.find_partition(PartitionType::Data(DataPartitionSubType::Nvs)))
+            .map_err(ImuCalibrationStoreError::PartitionTable)?
+            .ok_or(ImuCalibrationStoreError::NoPartition)?;
+        let range = partition.offset()..partition.offset() + partition.len();
+
+        Self::new(BlockingAsync::new(storage), range)
+    }
+}
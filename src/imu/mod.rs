@@ -0,0 +1,67 @@
+mod bmi270;
+mod calibration;
+mod motion;
+mod orientation;
+mod service;
+
+pub use bmi270::*;
+pub use calibration::*;
+pub use motion::*;
+pub use orientation::*;
+pub use service::*;
+
+use crate::i2c::{BlockingI2cDeviceWrapper, SharedI2cDevice, SystemI2cBus};
+use bmi2::{Bmi2, I2cAddr, config::BMI270_CONFIG_FILE, interface::I2cInterface, types::Burst};
+use defmt::{info, warn};
+
+pub type I2cDevice = SharedI2cDevice<SystemI2cBus>;
+pub type I2cError = <I2cDevice as embedded_hal_async::i2c::ErrorType>::Error;
+pub type Imu = Bmi2<I2cInterface<BlockingI2cDeviceWrapper<I2cDevice>>, embassy_time::Delay, 256>;
+pub type AsyncImu = Bmi270<I2cDevice>;
+
+/// Initialise the IMU with the accelerometer and gyroscope enabled, and the calibration saved in `store` applied.
+///
+/// `store` would usually be [`ImuCalibrationStore::in_flash`]. A missing or unreadable calibration is not an error,
+/// the IMU is left uncalibrated and a warning logged (see [`Bmi270::calibrate`] to create one).
+pub async fn init<S: embedded_storage_async::nor_flash::NorFlash>(
+    i2c: I2cDevice,
+    config: &SensorConfig,
+    store: &mut ImuCalibrationStore<S>,
+) -> Result<AsyncImu, Bmi270Error<I2cError>> {
+    let mut imu = Bmi270::new(i2c);
+
+    imu.init(&BMI270_CONFIG_FILE).await?;
+    imu.configure(config).await?;
+    imu.enable_sensors(true, true, false).await?;
+
+    match store.load().await {
+        Ok(Some(calibration)) => {
+            imu.set_calibration(&calibration).await?;
+            info!("IMU calibration applied: {}", calibration);
+        }
+        Ok(None) => warn!("No IMU calibration saved"),
+        Err(_) => warn!("Failed to read IMU calibration"),
+    }
+
+    Ok(imu)
+}
+
+/// Initialise the IMU using the blocking [`bmi2`] driver, without calibration.
+///
+/// This blocks the executor (and holds the system bus) for the whole config upload, prefer [`init`].
+pub async fn init_blocking(i2c: I2cDevice) -> Result<Imu, bmi2::types::Error<I2cError>> {
+    let i2c = BlockingI2cDeviceWrapper::new(i2c);
+
+    let mut imu = Bmi2::new_i2c(
+        i2c,
+        embassy_time::Delay,
+        I2cAddr::Alternative,
+        Burst::new(255),
+    );
+
+    info!("IMU chip ID: {}", imu.get_chip_id()?);
+
+    imu.init(&BMI270_CONFIG_FILE)?;
+
+    Ok(imu)
+}
@@ -0,0 +1,351 @@
+//! Motion and gesture events.
+//!
+//! Most events are detected by the feature engine of the BMI270, which runs from the config file uploaded at init. The
+//! feature locations here are those of the standard config file ([`bmi2::config::BMI270_CONFIG_FILE`]). That has no
+//! tap detector, so taps are instead detected from the accelerometer samples streamed by
+//! [`ImuService`](super::ImuService), using [`TapDetector`].
+
+use super::{Bmi270, ImuSample, SensorConfig, register};
+use defmt::{Format, debug, warn};
+use embassy_sync::{blocking_mutex::raw::RawMutex, channel::Sender};
+use embassy_time::{Duration, Instant};
+use getset::Getters;
+use tildagon_fusion::{Tap, TapConfig, TapDetector};
+
+/// Feature engine locations for the standard BMI270 config file, as (page, offset)
+mod feature {
+    pub const STEP_COUNTER_OUT: (u8, u8) = (0, 0x00);
+    pub const WRIST_GESTURE_OUT: (u8, u8) = (0, 0x06);
+    pub const ANY_MOTION: (u8, u8) = (1, 0x0C);
+    pub const NO_MOTION: (u8, u8) = (2, 0x00);
+    pub const SIG_MOTION: (u8, u8) = (2, 0x04);
+    pub const STEP_COUNTER: (u8, u8) = (6, 0x0C);
+    pub const WRIST_GESTURE: (u8, u8) = (6, 0x04);
+    pub const WRIST_WEAR_WAKE_UP: (u8, u8) = (7, 0x00);
+}
+
+/// Enable bits of the features, as (offset from the start of the feature, mask)
+mod enable {
+    pub const SIG_MOTION: (u8, u16) = (0x0A, 0x0001);
+    pub const STEP_COUNTER: (u8, u16) = (0x00, 0x1800);
+    pub const WRIST_GESTURE: (u8, u16) = (0x00, 0x0020);
+    pub const WRIST_WEAR_WAKE_UP: (u8, u16) = (0x00, 0x0010);
+}
+
+/// Bits in `INT_STATUS_0` and `INT1_MAP_FEAT`
+mod status {
+    pub const SIG_MOTION: u8 = 0x01;
+    pub const STEP_COUNTER: u8 = 0x02;
+    pub const WRIST_WEAR_WAKE_UP: u8 = 0x08;
+    pub const WRIST_GESTURE: u8 = 0x10;
+    pub const NO_MOTION: u8 = 0x20;
+    pub const ANY_MOTION: u8 = 0x40;
+}
+
+/// Value of the wrist gesture output for a shake
+const WRIST_GESTURE_SHAKE: u8 = 0x03;
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Shake,
+
+    /// The badge was tilted up towards the wearer, as if to look at it
+    WristTilt,
+
+    /// Sustained movement consistent with the wearer walking or travelling
+    SignificantMotion,
+
+    /// The step count changed, contains the total since the IMU was initialised
+    Steps(u32),
+
+    /// Movement started after a period of no motion
+    Started,
+
+    /// No movement for the configured duration
+    Stopped,
+
+    /// A single tap on the badge, reported once it can no longer be the start of a double tap
+    Tap,
+
+    DoubleTap,
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Getters)]
+pub struct MotionEvent {
+    #[getset(get = "pub")]
+    time: Instant,
+
+    #[getset(get = "pub")]
+    motion: Motion,
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct MotionConfig {
+    pub step_counter: bool,
+
+    /// Report [`Motion::Started`] and [`Motion::Stopped`]
+    pub motion: bool,
+
+    /// Acceleration change needed to count as motion, in mg
+    pub motion_threshold_mg: u16,
+
+    /// How long the acceleration must be above (or below) the threshold to start (or stop) motion
+    pub motion_duration: Duration,
+
+    pub significant_motion: bool,
+
+    /// Report [`Motion::Shake`], detected by the wrist gesture feature
+    pub shake: bool,
+
+    /// Report [`Motion::WristTilt`], detected by the wrist wear wake-up feature
+    pub wrist_tilt: bool,
+
+    /// Report [`Motion::Tap`] and [`Motion::DoubleTap`], detected from the samples passed to
+    /// [`MotionDetector::handle_sample`]
+    pub tap: bool,
+
+    /// Change in acceleration between consecutive samples that counts as a tap, in mg
+    pub tap_threshold_mg: u16,
+
+    /// Longest a tap may last, anything longer is the badge being handled
+    pub tap_max_duration: Duration,
+
+    /// Time after a tap for the badge to stop ringing, before another tap is looked for
+    pub tap_quiet: Duration,
+
+    /// Longest gap between the two taps of a double tap
+    pub double_tap_window: Duration,
+}
+
+impl Default for MotionConfig {
+    fn default() -> Self {
+        Self {
+            step_counter: true,
+            motion: true,
+            motion_threshold_mg: 80,
+            motion_duration: Duration::from_millis(1000),
+            significant_motion: true,
+            shake: true,
+            wrist_tilt: true,
+            tap: true,
+            tap_threshold_mg: 750,
+            tap_max_duration: Duration::from_millis(40),
+            tap_quiet: Duration::from_millis(30),
+            double_tap_window: Duration::from_millis(300),
+        }
+    }
+}
+
+/// Produces [`MotionEvent`]s from the IMU feature engine and accelerometer samples.
+pub struct MotionDetector {
+    config: MotionConfig,
+    steps: Option<u32>,
+    moving: Option<bool>,
+    taps: TapDetector,
+}
+
+impl MotionDetector {
+    /// Enable the feature engine for the configured features and map them to the IMU interrupt.
+    ///
+    /// `sensors` is the configuration of the samples that will be passed to [`MotionDetector::handle_sample`].
+    pub async fn new<I2C, E>(
+        imu: &mut Bmi270<I2C>,
+        sensors: &SensorConfig,
+        config: MotionConfig,
+    ) -> Result<Self, E>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let mut map = 0;
+
+        // Duration is in 20 ms units, threshold in 1/2048 g
+        let duration = (config.motion_duration.as_millis() / 20).min(0x1FFF) as u16;
+        let threshold = (config.motion_threshold_mg as u32 * 2048 / 1000).min(0x7FF) as u16;
+        for (page, offset) in [feature::ANY_MOTION, feature::NO_MOTION] {
+            // Select all axes, enable bit is the top bit of the threshold
+            let word_0 = duration | 0xE000;
+            let word_1 = threshold | ((config.motion as u16) << 15);
+
+            let mut data = [0u8; 4];
+            data[..2].copy_from_slice(&word_0.to_le_bytes());
+            data[2..].copy_from_slice(&word_1.to_le_bytes());
+            imu.write_feature(page, offset, &data).await?;
+        }
+        if config.motion {
+            map |= status::ANY_MOTION | status::NO_MOTION;
+        }
+
+        for (feature, enable, status, enabled) in [
+            (
+                feature::STEP_COUNTER,
+                enable::STEP_COUNTER,
+                status::STEP_COUNTER,
+                config.step_counter,
+            ),
+            (
+                feature::SIG_MOTION,
+                enable::SIG_MOTION,
+                status::SIG_MOTION,
+                config.significant_motion,
+            ),
+            (
+                feature::WRIST_GESTURE,
+                enable::WRIST_GESTURE,
+                status::WRIST_GESTURE,
+                config.shake,
+            ),
+            (
+                feature::WRIST_WEAR_WAKE_UP,
+                enable::WRIST_WEAR_WAKE_UP,
+                status::WRIST_WEAR_WAKE_UP,
+                config.wrist_tilt,
+            ),
+        ] {
+            set_feature_enable(imu, feature, enable, enabled).await?;
+            if enabled {
+                map |= status;
+            }
+        }
+
+        imu.write_register(register::INT1_MAP_FEAT, map).await?;
+
+        let period = sensors.accel_odr.period().as_micros();
+        let readings = |d: Duration| (d.as_micros() / period).clamp(1, u16::MAX as u64) as u16;
+        let taps = TapDetector::new(TapConfig {
+            threshold: (config.tap_threshold_mg as f32 * sensors.accel_range.lsb_per_g() / 1000.0)
+                as i32,
+            max_duration: readings(config.tap_max_duration),
+            quiet: readings(config.tap_quiet),
+            double_tap_window: readings(config.double_tap_window),
+        });
+
+        Ok(Self {
+            config,
+            steps: None,
+            moving: None,
+            taps,
+        })
+    }
+
+    pub fn config(&self) -> &MotionConfig {
+        &self.config
+    }
+
+    /// The most recently read step count.
+    pub fn steps(&self) -> Option<u32> {
+        self.steps
+    }
+
+    /// Check the feature engine for events.
+    ///
+    /// Call this on each system interrupt, the interrupt status is cleared when read so events are only reported once.
+    pub async fn poll<I2C, E, M: RawMutex, const N: usize>(
+        &mut self,
+        imu: &mut Bmi270<I2C>,
+        sender: &Sender<'_, M, MotionEvent, N>,
+    ) -> Result<(), E>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let time = Instant::now();
+        let int_status = imu.read_register(register::INT_STATUS_0).await?;
+
+        // Any motion is reported repeatedly while moving, only the transitions are of interest
+        if int_status & status::ANY_MOTION != 0 && self.moving != Some(true) {
+            self.moving = Some(true);
+            send(sender, time, Motion::Started);
+        }
+        if int_status & status::NO_MOTION != 0 && self.moving != Some(false) {
+            self.moving = Some(false);
+            send(sender, time, Motion::Stopped);
+        }
+
+        if int_status & status::SIG_MOTION != 0 {
+            send(sender, time, Motion::SignificantMotion);
+        }
+
+        if int_status & status::WRIST_WEAR_WAKE_UP != 0 {
+            send(sender, time, Motion::WristTilt);
+        }
+
+        if int_status & status::WRIST_GESTURE != 0 {
+            let (page, offset) = feature::WRIST_GESTURE_OUT;
+            let mut gesture = [0u8; 2];
+            imu.read_feature(page, offset, &mut gesture).await?;
+
+            match gesture[0] {
+                WRIST_GESTURE_SHAKE => send(sender, time, Motion::Shake),
+                other => debug!("Ignoring wrist gesture {}", other),
+            }
+        }
+
+        if int_status & status::STEP_COUNTER != 0
+            || (self.config.step_counter && self.steps.is_none())
+        {
+            let (page, offset) = feature::STEP_COUNTER_OUT;
+            let mut count = [0u8; 4];
+            imu.read_feature(page, offset, &mut count).await?;
+            let count = u32::from_le_bytes(count);
+
+            if self.steps != Some(count) {
+                self.steps = Some(count);
+                send(sender, time, Motion::Steps(count));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look for taps in an accelerometer sample, call this with every sample from the
+    /// [`ImuService`](super::ImuService) in order.
+    ///
+    /// Taps are short, so this needs a reasonably high data rate to see them (100 Hz or more).
+    pub fn handle_sample<M: RawMutex, const N: usize>(
+        &mut self,
+        sample: &ImuSample,
+        sender: &Sender<'_, M, MotionEvent, N>,
+    ) {
+        if !self.config.tap {
+            return;
+        }
+
+        match self.taps.push(sample.data().acc) {
+            Some(Tap::Single) => send(sender, *sample.time(), Motion::Tap),
+            Some(Tap::Double) => send(sender, *sample.time(), Motion::DoubleTap),
+            None => {}
+        }
+    }
+}
+
+/// Set or clear the enable bits of a feature, leaving its other settings as they are.
+async fn set_feature_enable<I2C, E>(
+    imu: &mut Bmi270<I2C>,
+    (page, offset): (u8, u8),
+    (enable_offset, mask): (u8, u16),
+    enabled: bool,
+) -> Result<(), E>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    let mut word = [0u8; 2];
+    imu.read_feature(page, offset + enable_offset, &mut word)
+        .await?;
+
+    let word = match enabled {
+        true => u16::from_le_bytes(word) | mask,
+        false => u16::from_le_bytes(word) & !mask,
+    };
+    imu.write_feature(page, offset + enable_offset, &word.to_le_bytes())
+        .await
+}
+
+fn send<M: RawMutex, const N: usize>(
+    sender: &Sender<'_, M, MotionEvent, N>,
+    time: Instant,
+    motion: Motion,
+) {
+    debug!("Motion: {}", motion);
+    if sender.try_send(MotionEvent { time, motion }).is_err() {
+        warn!("Motion event channel full, dropping {}", motion);
+    }
+}
@@ -0,0 +1,212 @@
+//! Attitude estimation by fusing accelerometer and gyroscope samples with a complementary filter.
+//!
+//! Angles are in degrees, in the axes of the IMU. Roll and pitch are corrected by gravity, yaw is integrated from the
+//! gyroscope alone and so will drift.
+//!
+//! The filter itself is in [`tildagon_fusion`], which has no hardware dependencies so is tested on the host.
+//!
+//! Readings are expected to already be corrected by the IMU's offset registers, see
+//! [`Bmi270::calibrate`](super::Bmi270::calibrate), no further bias is removed here.
+
+use super::{AccelRange, GyroRange, ImuSample};
+use defmt::{Format, debug};
+use embassy_time::Instant;
+use micromath::F32Ext;
+use tildagon_fusion::complementary_update;
+
+pub use tildagon_fusion::Attitude;
+
+/// Which way up the badge is, judged from the direction of gravity.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum DiscreteOrientation {
+    /// Lying flat, display upwards (+Z up)
+    FaceUp,
+
+    /// Lying flat, display downwards (-Z up)
+    FaceDown,
+
+    /// Upright (+Y up)
+    Portrait,
+
+    /// Upright, upside down (-Y up)
+    PortraitInverted,
+
+    /// On its side (+X up)
+    LandscapeLeft,
+
+    /// On its side (-X up)
+    LandscapeRight,
+}
+
+impl DiscreteOrientation {
+    /// Unit vector of the axis pointing up in this orientation.
+    pub(crate) fn up(&self) -> [f32; 3] {
+        match self {
+            Self::FaceUp => [0.0, 0.0, 1.0],
+            Self::FaceDown => [0.0, 0.0, -1.0],
+            Self::Portrait => [0.0, 1.0, 0.0],
+            Self::PortraitInverted => [0.0, -1.0, 0.0],
+            Self::LandscapeLeft => [1.0, 0.0, 0.0],
+            Self::LandscapeRight => [-1.0, 0.0, 0.0],
+        }
+    }
+
+    const ALL: [Self; 6] = [
+        Self::FaceUp,
+        Self::FaceDown,
+        Self::Portrait,
+        Self::PortraitInverted,
+        Self::LandscapeLeft,
+        Self::LandscapeRight,
+    ];
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq)]
+pub struct OrientationConfig {
+    /// Ranges the sensors have been configured with
+    pub accel_range: AccelRange,
+    pub gyro_range: GyroRange,
+
+    /// Weight given to the gyroscope over the accelerometer, between 0 and 1
+    pub gyro_weight: f32,
+
+    /// The accelerometer is only used for correction when its magnitude is within this of 1 g, i.e. when the badge
+    /// is not otherwise accelerating
+    pub accel_tolerance_g: f32,
+
+    /// How far past the halfway point between two orientations the badge must be tilted before the discrete
+    /// orientation changes, in degrees
+    pub hysteresis: f32,
+
+    /// Smoothing of the gravity vector used for the discrete orientation, between 0 (none) and 1
+    pub gravity_smoothing: f32,
+}
+
+impl Default for OrientationConfig {
+    fn default() -> Self {
+        Self {
+            accel_range: AccelRange::G4,
+            gyro_range: GyroRange::Dps2000,
+            gyro_weight: 0.98,
+            accel_tolerance_g: 0.2,
+            hysteresis: 15.0,
+            gravity_smoothing: 0.9,
+        }
+    }
+}
+
+pub struct OrientationEstimator {
+    config: OrientationConfig,
+    attitude: Option<Attitude>,
+    gravity: Option<[f32; 3]>,
+    orientation: Option<DiscreteOrientation>,
+    last_time: Option<Instant>,
+}
+
+impl OrientationEstimator {
+    pub fn new(config: OrientationConfig) -> Self {
+        Self {
+            config,
+            attitude: None,
+            gravity: None,
+            orientation: None,
+            last_time: None,
+        }
+    }
+
+    pub fn config(&self) -> &OrientationConfig {
+        &self.config
+    }
+
+    pub fn attitude(&self) -> Option<Attitude> {
+        self.attitude
+    }
+
+    pub fn orientation(&self) -> Option<DiscreteOrientation> {
+        self.orientation
+    }
+
+    /// Forget the current estimate, e.g. after a gap in samples.
+    pub fn reset(&mut self) {
+        self.attitude = None;
+        self.gravity = None;
+        self.orientation = None;
+        self.last_time = None;
+    }
+
+    /// Update the estimate with a new sample.
+    ///
+    /// Returns the new discrete orientation if it changed.
+    pub fn update(&mut self, sample: &ImuSample) -> Option<DiscreteOrientation> {
+        let data = sample.data();
+        let lsb_per_g = self.config.accel_range.lsb_per_g();
+        let lsb_per_dps = self.config.gyro_range.lsb_per_dps();
+
+        let acc = data.acc.map(|a| a as f32 / lsb_per_g);
+        let gyr = data.gyr.map(|g| g as f32 / lsb_per_dps);
+
+        let dt = match self.last_time {
+            Some(last) => sample.time().saturating_duration_since(last).as_micros() as f32 / 1e6,
+            None => 0.0,
+        };
+        self.last_time = Some(*sample.time());
+
+        self.update_attitude(acc, gyr, dt);
+        self.update_orientation(acc)
+    }
+
+    fn update_attitude(&mut self, acc: [f32; 3], gyr: [f32; 3], dt: f32) {
+        self.attitude = Some(complementary_update(
+            self.attitude,
+            acc,
+            gyr,
+            dt,
+            self.config.gyro_weight,
+            self.config.accel_tolerance_g,
+        ));
+    }
+
+    fn update_orientation(&mut self, acc: [f32; 3]) -> Option<DiscreteOrientation> {
+        let smoothing = self.config.gravity_smoothing.clamp(0.0, 0.99);
+        let gravity = match self.gravity {
+            None => acc,
+            Some(g) => [0, 1, 2].map(|i| g[i] * smoothing + acc[i] * (1.0 - smoothing)),
+        };
+        self.gravity = Some(gravity);
+
+        let magnitude = gravity.iter().map(|g| g * g).sum::<f32>().sqrt();
+        if magnitude < 0.5 {
+            // In free fall or being thrown about, no sense of up
+            return None;
+        }
+
+        // Cosine of the angle between gravity and the up axis of an orientation
+        let alignment = |o: &DiscreteOrientation| {
+            let up = o.up();
+            (0..3).map(|i| gravity[i] * up[i]).sum::<f32>() / magnitude
+        };
+
+        let best = DiscreteOrientation::ALL
+            .iter()
+            .copied()
+            .max_by(|a, b| alignment(a).total_cmp(&alignment(b)))?;
+
+        // Changing requires the new orientation to be within (45 - hysteresis) degrees of vertical
+        let required = (45.0 - self.config.hysteresis.clamp(0.0, 40.0))
+            .to_radians()
+            .cos();
+
+        let changed = match self.orientation {
+            None => true,
+            Some(current) => best != current && alignment(&best) >= required,
+        };
+
+        if changed {
+            debug!("Orientation {} -> {}", self.orientation, best);
+            self.orientation = Some(best);
+            Some(best)
+        } else {
+            None
+        }
+    }
+}
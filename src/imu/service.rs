@@ -0,0 +1,195 @@
+//! Interrupt driven streaming of IMU samples.
+//!
+//! The BMI270 buffers samples in its FIFO and raises its interrupt (via the AW9523, so reported through
+//! [`SystemInterrupt`](crate::system_interrupt::SystemInterrupt)) once a batch is ready. The whole batch is then read in
+//! as few transactions as possible, timestamped and sent on a channel.
+
+use super::{Bmi270, FIFO_FRAME_SIZE, RawSensorData, SensorConfig, register};
+use crate::pins::{ExpanderPin, ImuPins};
+use defmt::{Format, debug, warn};
+use embassy_sync::{blocking_mutex::raw::RawMutex, channel::Sender};
+use embassy_time::{Duration, Instant};
+use embedded_aw9523::{Input, InputRegisters, InputRegistersError};
+use embedded_hal::digital::PinState;
+use getset::Getters;
+
+/// Largest number of frames read from the FIFO in one transaction
+const MAX_FRAMES_PER_READ: usize = 16;
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct ImuServiceConfig {
+    /// Sensor configuration, the gyroscope output data rate is set to match the accelerometer so each FIFO frame
+    /// contains both
+    pub sensors: SensorConfig,
+
+    /// Number of samples to buffer in the IMU before raising an interrupt
+    pub batch_size: u16,
+}
+
+impl Default for ImuServiceConfig {
+    fn default() -> Self {
+        Self {
+            sensors: SensorConfig::default(),
+            batch_size: 10,
+        }
+    }
+}
+
+#[derive(Debug, Format)]
+pub enum ImuServiceError<E> {
+    I2c(E),
+    InputRegisters(InputRegistersError),
+}
+
+impl<E> From<E> for ImuServiceError<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Getters)]
+pub struct ImuSample {
+    /// Estimated time at which the sample was taken
+    #[getset(get = "pub")]
+    time: Instant,
+
+    #[getset(get = "pub")]
+    data: RawSensorData,
+}
+
+pub struct ImuService<I2C> {
+    imu: Bmi270<I2C>,
+    int: Input<I2C>,
+    period: Duration,
+    last_sample: Option<Instant>,
+}
+
+impl<I2C, E> ImuService<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// Configure an initialised IMU for FIFO streaming, and enable the expander interrupt for its interrupt pin.
+    pub async fn new(
+        mut imu: Bmi270<I2C>,
+        pins: ImuPins<I2C>,
+        config: ImuServiceConfig,
+    ) -> Result<Self, E> {
+        let mut sensors = config.sensors;
+        sensors.gyro_odr = sensors.accel_odr;
+
+        imu.configure(&sensors).await?;
+
+        // Store filtered data, at the configured data rate
+        imu.write_register(register::FIFO_DOWNS, 0x88).await?;
+
+        let watermark = (config.batch_size.max(1) as usize * FIFO_FRAME_SIZE) as u16;
+        imu.write_registers(register::FIFO_WTM_0, &watermark.to_le_bytes())
+            .await?;
+
+        // Keep the newest samples if the FIFO fills up
+        imu.write_register(register::FIFO_CONFIG_0, 0x00).await?;
+
+        // Headerless frames containing the accelerometer and gyroscope
+        imu.write_register(register::FIFO_CONFIG_1, 0xC0).await?;
+
+        // INT1 as an active high push-pull output, level triggered while the watermark is reached
+        imu.write_register(register::INT1_IO_CTRL, 0x0A).await?;
+        imu.write_register(register::INT_LATCH, 0x00).await?;
+        imu.write_register(register::INT_MAP_DATA, 0x02).await?;
+
+        // The expander starts with all of its interrupts disabled, so would not pass the watermark on
+        let int = ExpanderPin::of(&pins.imu_int);
+        int.modify(imu.i2c(), int.interrupt_register(), false)
+            .await?;
+
+        imu.enable_sensors(true, true, false).await?;
+        imu.flush_fifo().await?;
+
+        Ok(Self {
+            imu,
+            int: pins.imu_int,
+            period: sensors.accel_odr.period(),
+            last_sample: None,
+        })
+    }
+
+    pub fn imu(&mut self) -> &mut Bmi270<I2C> {
+        &mut self.imu
+    }
+
+    /// Read the FIFO if the IMU interrupt is asserted.
+    ///
+    /// Call this with each new reading of the input registers, as done for buttons.
+    /// Returns the number of samples sent.
+    pub async fn update<M: RawMutex, const N: usize>(
+        &mut self,
+        regs: &InputRegisters,
+        sender: &Sender<'_, M, ImuSample, N>,
+    ) -> Result<usize, ImuServiceError<E>> {
+        match regs
+            .pin_state(&self.int)
+            .map_err(ImuServiceError::InputRegisters)?
+        {
+            PinState::High => self.drain(sender).await,
+            PinState::Low => Ok(0),
+        }
+    }
+
+    /// Read all complete frames from the FIFO and send them.
+    ///
+    /// Samples are dropped (with a warning) if the channel is full, so that the system bus is never held up by a slow
+    /// consumer. Returns the number of samples sent.
+    pub async fn drain<M: RawMutex, const N: usize>(
+        &mut self,
+        sender: &Sender<'_, M, ImuSample, N>,
+    ) -> Result<usize, ImuServiceError<E>> {
+        let now = Instant::now();
+        let frames = self.imu.fifo_length().await? as usize / FIFO_FRAME_SIZE;
+        if frames == 0 {
+            return Ok(0);
+        }
+
+        // The last frame was sampled most recently, work back from there at the data rate
+        let mut time = now
+            .checked_sub(self.period * (frames as u32 - 1))
+            .unwrap_or(Instant::MIN);
+        if let Some(last) = self.last_sample {
+            time = time.max(last + self.period);
+        }
+
+        let mut sent = 0;
+        let mut remaining = frames;
+        let mut buffer = [0u8; FIFO_FRAME_SIZE * MAX_FRAMES_PER_READ];
+
+        while remaining > 0 {
+            let count = remaining.min(MAX_FRAMES_PER_READ);
+            let data = &mut buffer[..count * FIFO_FRAME_SIZE];
+            self.imu.read_fifo(data).await?;
+
+            for frame in data.chunks_exact(FIFO_FRAME_SIZE) {
+                // Frames hold the gyroscope before the accelerometer
+                let mut ordered = [0u8; FIFO_FRAME_SIZE];
+                ordered[..6].copy_from_slice(&frame[6..]);
+                ordered[6..].copy_from_slice(&frame[..6]);
+
+                let sample = ImuSample {
+                    time,
+                    data: RawSensorData::from_le_bytes(&ordered),
+                };
+
+                match sender.try_send(sample) {
+                    Ok(()) => sent += 1,
+                    Err(_) => warn!("IMU sample channel full, dropping sample"),
+                }
+
+                self.last_sample = Some(time);
+                time += self.period;
+            }
+
+            remaining -= count;
+        }
+
+        debug!("Read {} IMU samples", frames);
+        Ok(sent)
+    }
+}
@@ -1,9 +1,9 @@
-use crate::pins::LedPins;
-use embedded_aw9523::{Output, async_traits::digital::OutputPin};
+use crate::pins::{LedPins, LockedOutput, PinError};
+use embedded_aw9523::async_traits::digital::OutputPin;
 use embedded_hal::digital::PinState;
 
 pub struct OnboardLedPower<I2C> {
-    pwr: Output<I2C>,
+    pwr: LockedOutput<I2C>,
 }
 
 impl<I2C> OnboardLedPower<I2C>
@@ -16,10 +16,7 @@ where
         }
     }
 
-    pub async fn set(
-        &mut self,
-        on: bool,
-    ) -> Result<(), <Output<I2C> as embedded_hal::digital::ErrorType>::Error> {
+    pub async fn set(&mut self, on: bool) -> Result<(), PinError<I2C>> {
         self.pwr
             .set_state(match on {
                 true => PinState::High,
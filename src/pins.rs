@@ -1,6 +1,152 @@
-use crate::i2c::{SharedI2cDevice, SharingRawMutex, SystemI2cBus};
+use crate::{
+    hexpansions::HexpansionPort,
+    i2c::{SharedI2cDevice, SharingRawMutex, SystemI2cBus},
+};
+use defmt::Format;
 use embassy_sync::mutex::Mutex;
-use embedded_aw9523::{Address, Aw9523, Input, InputRegisters, Output, PinConfiguration};
+use embedded_aw9523::{
+    Address, Aw9523, DescriptorExt, Input, InputRegisters, Output, Pin, PinConfiguration, Port,
+    async_traits::digital::OutputPin,
+};
+
+pub type PinError<I2C> = <Output<I2C> as embedded_hal::digital::ErrorType>::Error;
+
+/// Held across the read-modify-write register updates made by this crate.
+///
+/// [`embedded_aw9523`] reads and writes a register in separate bus transactions, so two updates of the same register
+/// made concurrently can lose one of the changes. Updates made by this crate hold this lock, application code updating
+/// expander registers directly should do the same.
+pub static EXPANDER_LOCK: Mutex<SharingRawMutex, ()> = Mutex::new(());
+
+/// Location of a pin on the AW9523 expanders, for register level access that the pin types do not provide.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct ExpanderPin {
+    /// I2C address of the expander
+    pub address: u8,
+    pub port: u8,
+    pub pin: u8,
+}
+
+impl ExpanderPin {
+    pub const fn new(address: u8, port: u8, pin: u8) -> Self {
+        Self { address, port, pin }
+    }
+
+    /// Location of a pin handed out by the expander driver.
+    pub fn of<PIN: DescriptorExt>(pin: &PIN) -> Self {
+        Self::new(
+            pin.address() as u8,
+            pin.port() as u8,
+            (pin.pin() as u8).trailing_zeros() as u8,
+        )
+    }
+
+    /// Set or clear the bit for this pin in a register, holding [`EXPANDER_LOCK`] between the read and the write.
+    pub async fn modify<I2C, E>(&self, i2c: &mut I2C, register: u8, set: bool) -> Result<(), E>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let _lock = EXPANDER_LOCK.lock().await;
+
+        let mut value = [0];
+        i2c.write_read(self.address, &[register], &mut value)
+            .await?;
+        let value = match set {
+            true => value[0] | self.mask(),
+            false => value[0] & !self.mask(),
+        };
+        i2c.write(self.address, &[register, value]).await
+    }
+
+    /// Bit for this pin in the per port registers
+    pub const fn mask(&self) -> u8 {
+        1 << self.pin
+    }
+
+    /// LED mode switch register, a cleared bit puts the pin into constant current LED drive mode
+    pub const fn led_mode_register(&self) -> u8 {
+        0x12 + self.port
+    }
+
+    /// Input level register
+    pub const fn input_register(&self) -> u8 {
+        self.port
+    }
+
+    /// Output level register
+    pub const fn output_register(&self) -> u8 {
+        0x02 + self.port
+    }
+
+    /// Direction register, a set bit makes the pin an input
+    pub const fn config_register(&self) -> u8 {
+        0x04 + self.port
+    }
+
+    /// Interrupt enable register, a cleared bit raises the interrupt when the input changes
+    pub const fn interrupt_register(&self) -> u8 {
+        0x06 + self.port
+    }
+
+    /// LED current control register, only used in LED drive mode
+    pub const fn dim_register(&self) -> u8 {
+        match (self.port, self.pin) {
+            (0, pin) => 0x24 + pin,
+            (_, pin @ 0..=3) => 0x20 + pin,
+            (_, pin) => 0x2C + pin - 4,
+        }
+    }
+}
+
+/// Lets [`InputRegisters::pin_state`] look up the pin.
+///
+/// Panics if the location is not on an AW9523, which only [`ExpanderPin::new`] with invalid values can cause.
+impl DescriptorExt for ExpanderPin {
+    fn address(&self) -> Address {
+        Address::try_from(self.address).expect("invalid expander address")
+    }
+
+    fn port(&self) -> Port {
+        Port::try_from(self.port).expect("invalid expander port")
+    }
+
+    fn pin(&self) -> Pin {
+        Pin::try_from(self.mask()).expect("invalid expander pin")
+    }
+}
+
+/// An expander output whose level is changed while holding [`EXPANDER_LOCK`].
+///
+/// Used for the outputs on 0x5A, which share their output register with low speed pins of hexpansions A and B that
+/// [`LowSpeedIo`](crate::hexpansions::LowSpeedIo) updates.
+pub struct LockedOutput<I2C> {
+    pin: Output<I2C>,
+}
+
+impl<I2C> LockedOutput<I2C> {
+    pub fn new(pin: Output<I2C>) -> Self {
+        Self { pin }
+    }
+}
+
+impl<I2C> embedded_hal::digital::ErrorType for LockedOutput<I2C> {
+    type Error = PinError<I2C>;
+}
+
+impl<I2C, E> OutputPin for LockedOutput<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    async fn set_low(&mut self) -> Result<(), Self::Error> {
+        let _lock = EXPANDER_LOCK.lock().await;
+        self.pin.set_low().await
+    }
+
+    async fn set_high(&mut self) -> Result<(), Self::Error> {
+        let _lock = EXPANDER_LOCK.lock().await;
+        self.pin.set_high().await
+    }
+}
 
 pub struct PinControl {
     system_bus: SharedI2cDevice<SystemI2cBus>,
@@ -24,10 +170,11 @@ impl PinControl {
         let pins59 = addr59.pins();
         let pins5a = addr5a.pins();
 
-        let pins = Pins::new(pins58, pins59, pins5a).await?;
+        let mut system_bus = SharedI2cDevice::new(system_bus);
+        let pins = Pins::new(&mut system_bus, pins58, pins59, pins5a).await?;
 
         Ok(Self {
-            system_bus: SharedI2cDevice::new(system_bus),
+            system_bus,
             pins: Some(pins),
         })
     }
@@ -71,35 +218,39 @@ where
     SysI2C: embedded_hal_async::i2c::I2c<Error = E>,
 {
     async fn new(
+        i2c: &mut SysI2C,
         addr58_pins: embedded_aw9523::Pins<SysI2C>,
         addr59_pins: embedded_aw9523::Pins<SysI2C>,
         addr5a_pins: embedded_aw9523::Pins<SysI2C>,
     ) -> Result<Self, E> {
+        let hexpansion_detect = HexpansionDetectPins {
+            a: addr5a_pins.port1_pin4,
+            b: addr5a_pins.port1_pin5,
+            c: addr59_pins.port1_pin0,
+            d: addr59_pins.port1_pin1,
+            e: addr59_pins.port1_pin2,
+            f: addr59_pins.port1_pin3,
+        };
+        hexpansion_detect.disable_all(i2c).await?;
+
         Ok(Self {
             vbus: VbusPins {
-                vbus_sw: addr5a_pins.port0_pin4.try_into_output().await?,
+                vbus_sw: LockedOutput::new(addr5a_pins.port0_pin4.try_into_output().await?),
             },
             usb: UsbPins {
-                usb_select: addr5a_pins.port0_pin5.try_into_output().await?,
+                usb_select: LockedOutput::new(addr5a_pins.port0_pin5.try_into_output().await?),
             },
             imu: ImuPins {
                 imu_int: addr58_pins.port0_pin1,
             },
             led: LedPins {
-                power_enable: addr5a_pins.port0_pin2.try_into_output().await?,
+                power_enable: LockedOutput::new(addr5a_pins.port0_pin2.try_into_output().await?),
             },
             front_board: FrontBoardPins {
                 ls_1: addr5a_pins.port1_pin7,
                 ls_2: addr5a_pins.port1_pin6,
             },
-            hexpansion_detect: HexpansionDetectPins {
-                a: addr5a_pins.port1_pin4.try_into_output().await?,
-                b: addr5a_pins.port1_pin5.try_into_output().await?,
-                c: addr59_pins.port1_pin0.try_into_output().await?,
-                d: addr59_pins.port1_pin1.try_into_output().await?,
-                e: addr59_pins.port1_pin2.try_into_output().await?,
-                f: addr59_pins.port1_pin3.try_into_output().await?,
-            },
+            hexpansion_detect,
             buttons: ButtonPins {
                 btn1: addr5a_pins.port0_pin6,
                 btn2: addr5a_pins.port0_pin7,
@@ -155,11 +306,11 @@ where
 }
 
 pub struct VbusPins<SysI2C> {
-    pub vbus_sw: Output<SysI2C>,
+    pub vbus_sw: LockedOutput<SysI2C>,
 }
 
 pub struct UsbPins<SysI2C> {
-    pub usb_select: Output<SysI2C>,
+    pub usb_select: LockedOutput<SysI2C>,
 }
 
 pub struct ImuPins<SysI2C> {
@@ -167,7 +318,7 @@ pub struct ImuPins<SysI2C> {
 }
 
 pub struct LedPins<SysI2C> {
-    pub power_enable: Output<SysI2C>,
+    pub power_enable: LockedOutput<SysI2C>,
 }
 
 pub struct FrontBoardPins<SysI2C> {
@@ -175,13 +326,34 @@ pub struct FrontBoardPins<SysI2C> {
     pub ls_2: Input<SysI2C>,
 }
 
+/// Port power enables, which are also the detect inputs.
+///
+/// Every port is driven disabled when the pins are set up, then
+/// [`HexpansionPortControl`](crate::hexpansions::HexpansionPortControl) switches their direction to enable them.
 pub struct HexpansionDetectPins<SysI2C> {
-    pub a: Output<SysI2C>,
-    pub b: Output<SysI2C>,
-    pub c: Output<SysI2C>,
-    pub d: Output<SysI2C>,
-    pub e: Output<SysI2C>,
-    pub f: Output<SysI2C>,
+    pub a: Input<SysI2C>,
+    pub b: Input<SysI2C>,
+    pub c: Input<SysI2C>,
+    pub d: Input<SysI2C>,
+    pub e: Input<SysI2C>,
+    pub f: Input<SysI2C>,
+}
+
+impl<SysI2C> HexpansionDetectPins<SysI2C> {
+    /// Latch the outputs high before switching direction, so no port is briefly driven on.
+    async fn disable_all<I2C, E>(&self, i2c: &mut I2C) -> Result<(), E>
+    where
+        I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    {
+        let pins = [&self.a, &self.b, &self.c, &self.d, &self.e, &self.f].map(ExpanderPin::of);
+        for pin in &pins {
+            pin.modify(i2c, pin.output_register(), true).await?;
+        }
+        for pin in &pins {
+            pin.modify(i2c, pin.config_register(), false).await?;
+        }
+        Ok(())
+    }
 }
 
 pub struct ButtonPins<SysI2C> {
@@ -240,3 +412,36 @@ pub struct HexpansionFPins<SysI2C> {
     pub ls_4: Input<SysI2C>,
     pub ls_5: Input<SysI2C>,
 }
+
+/// The low speed pins of a hexpansion port, for use with [`LowSpeedIo`](crate::hexpansions::LowSpeedIo).
+pub trait HexpansionLsPins<SysI2C> {
+    const PORT: HexpansionPort;
+
+    /// LS_1 to LS_5, e.g. for finding their locations with [`ExpanderPin::of`]
+    fn inputs(&self) -> [&Input<SysI2C>; 5];
+
+    fn into_inputs(self) -> [Input<SysI2C>; 5];
+}
+
+macro_rules! impl_hexpansion_ls_pins {
+    ($pins:ident, $port:ident) => {
+        impl<SysI2C> HexpansionLsPins<SysI2C> for $pins<SysI2C> {
+            const PORT: HexpansionPort = HexpansionPort::$port;
+
+            fn inputs(&self) -> [&Input<SysI2C>; 5] {
+                [&self.ls_1, &self.ls_2, &self.ls_3, &self.ls_4, &self.ls_5]
+            }
+
+            fn into_inputs(self) -> [Input<SysI2C>; 5] {
+                [self.ls_1, self.ls_2, self.ls_3, self.ls_4, self.ls_5]
+            }
+        }
+    };
+}
+
+impl_hexpansion_ls_pins!(HexpansionAPins, A);
+impl_hexpansion_ls_pins!(HexpansionBPins, B);
+impl_hexpansion_ls_pins!(HexpansionCPins, C);
+impl_hexpansion_ls_pins!(HexpansionDPins, D);
+impl_hexpansion_ls_pins!(HexpansionEPins, E);
+impl_hexpansion_ls_pins!(HexpansionFPins, F);
@@ -1,5 +1,12 @@
-use crate::i2c::{SharedI2cBus, SharedI2cDevice, SystemI2cBus};
+use crate::{
+    i2c::{SharedI2cBus, SharedI2cDevice, SystemI2cBus},
+    pins::{LockedOutput, PinError, VbusPins},
+    usb::{UsbPort, UsbSwitch},
+};
 use bq25895::{Bq25895, Interface};
+use defmt::{Format, info, warn};
+use embedded_aw9523::async_traits::digital::OutputPin;
+use embedded_hal::digital::PinState;
 
 pub fn new_bq25895(
     i2c_system: &'static SharedI2cBus<SystemI2cBus>,
@@ -7,3 +14,262 @@ pub fn new_bq25895(
     let bq_interface = Interface::new(SharedI2cDevice::new(i2c_system));
     Bq25895::new(bq_interface)
 }
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum VbusMode {
+    /// Charge the battery from USB in, USB out is not powered
+    ChargeFromIn,
+
+    /// Boost the battery voltage onto USB out (OTG mode), USB in must not be supplying power
+    PowerOutFromBattery,
+
+    /// Pass USB in power through to USB out, whilst still charging the battery
+    PassThrough,
+}
+
+impl VbusMode {
+    fn usb_port(&self) -> UsbPort {
+        match self {
+            VbusMode::ChargeFromIn => UsbPort::In,
+            VbusMode::PowerOutFromBattery => UsbPort::Out,
+            VbusMode::PassThrough => UsbPort::In,
+        }
+    }
+
+    fn vbus_switch_closed(&self) -> bool {
+        match self {
+            VbusMode::ChargeFromIn => false,
+            VbusMode::PowerOutFromBattery => true,
+            VbusMode::PassThrough => true,
+        }
+    }
+
+    fn boost(&self) -> bool {
+        matches!(self, VbusMode::PowerOutFromBattery)
+    }
+}
+
+/// Reasons a mode change was refused.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum VbusInterlock {
+    /// USB in is supplying power, boosting onto the shared VBUS would fight it
+    InputPresent,
+
+    /// USB in is not supplying power, so there is nothing to pass through
+    NoInput,
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum VbusError<E, P> {
+    I2c(E),
+    Pin(P),
+    InvalidRegisterValue,
+    Interlock(VbusInterlock),
+}
+
+/// Control of the USB VBUS power path.
+///
+/// Combines the VBUS switch between the USB ports, the BQ25895 boost (OTG) converter and the USB data switch.
+pub struct Vbus<I2C> {
+    sw: LockedOutput<I2C>,
+    usb: UsbSwitch<I2C>,
+    bq: Bq25895<Interface<I2C>>,
+    mode: VbusMode,
+
+    /// False if the last change failed part way, leaving the switches in an unknown state
+    applied: bool,
+}
+
+impl<I2C, E> Vbus<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    /// Create the VBUS controller, starting in [`VbusMode::ChargeFromIn`].
+    ///
+    /// This drives the whole power path, so disables the boost converter and enables charging in the charger's
+    /// configuration, whatever it was set to before.
+    pub async fn new(
+        r: VbusPins<I2C>,
+        usb: UsbSwitch<I2C>,
+        bq: Bq25895<Interface<I2C>>,
+    ) -> Result<Self, VbusError<E, PinError<I2C>>> {
+        let mut vbus = Self {
+            sw: r.vbus_sw,
+            usb,
+            bq,
+            mode: VbusMode::ChargeFromIn,
+            applied: false,
+        };
+        vbus.apply(VbusMode::ChargeFromIn).await?;
+        Ok(vbus)
+    }
+
+    /// The current mode.
+    ///
+    /// If a change failed part way this is [`VbusMode::ChargeFromIn`] (boost is always disabled first) but the switches
+    /// may not match it, see [`Vbus::applied`].
+    pub fn mode(&self) -> VbusMode {
+        self.mode
+    }
+
+    /// Returns false if the last mode change failed part way, the next [`Vbus::set_mode`] drives everything again.
+    pub fn applied(&self) -> bool {
+        self.applied
+    }
+
+    /// Access to the charger, e.g. for feeding its watchdog or taking measurements.
+    ///
+    /// Do not modify the OTG or charge configuration through this, use [`Vbus::set_mode`] instead.
+    pub fn charger(&mut self) -> &mut Bq25895<Interface<I2C>> {
+        &mut self.bq
+    }
+
+    /// Access to the USB data switch, which is otherwise set to match the power mode.
+    pub fn usb_switch(&mut self) -> &mut UsbSwitch<I2C> {
+        &mut self.usb
+    }
+
+    /// Returns true if USB in is currently supplying good power to the charger.
+    pub async fn input_present(&mut self) -> Result<bool, VbusError<E, PinError<I2C>>> {
+        let (vbus_stat, pg_stat) = self.status().await?;
+        Ok(input_present(vbus_stat, pg_stat))
+    }
+
+    /// Re-check the current mode against the charger, falling back to [`VbusMode::ChargeFromIn`] if it no longer holds.
+    /// Call this periodically (e.g. every second), [`UsbManager::update`](crate::usb::UsbManager::update) does.
+    ///
+    /// Whilst boosting, VBUS usually reads as OTG so USB in cannot be seen directly. A supply appearing on USB in fights
+    /// the boost, which the charger reports as a boost fault (reading it clears it) or by seeing the input, and either
+    /// falls back. Pass through falls back once USB in goes away.
+    ///
+    /// Returns true if USB in is supplying power after any fall back.
+    pub async fn update(&mut self) -> Result<bool, VbusError<E, PinError<I2C>>> {
+        let (vbus_stat, pg_stat) = self.status().await?;
+
+        let fall_back = match self.mode {
+            VbusMode::ChargeFromIn => false,
+            VbusMode::PowerOutFromBattery => {
+                let faults = self
+                    .bq
+                    .reg_0_c()
+                    .read_async()
+                    .await
+                    .map_err(VbusError::I2c)?;
+                let boost_fault = faults
+                    .boost_fault()
+                    .map_err(|_| VbusError::InvalidRegisterValue)?;
+
+                input_present(vbus_stat, pg_stat) || boost_fault == bq25895::BoostModeStatus::Fault
+            }
+            VbusMode::PassThrough => !input_present(vbus_stat, pg_stat),
+        };
+
+        if !fall_back {
+            return Ok(input_present(vbus_stat, pg_stat));
+        }
+
+        warn!("VBUS {} no longer possible, falling back", self.mode);
+        self.apply(VbusMode::ChargeFromIn).await?;
+        self.input_present().await
+    }
+
+    async fn status(
+        &mut self,
+    ) -> Result<(bq25895::VbusStatus, bq25895::PowerGood), VbusError<E, PinError<I2C>>> {
+        let reg = self
+            .bq
+            .reg_0_b()
+            .read_async()
+            .await
+            .map_err(VbusError::I2c)?;
+
+        let vbus_stat = reg
+            .vbus_stat()
+            .map_err(|_| VbusError::InvalidRegisterValue)?;
+        let pg_stat = reg.pg_stat().map_err(|_| VbusError::InvalidRegisterValue)?;
+        Ok((vbus_stat, pg_stat))
+    }
+
+    /// Change the power path, refusing modes that conflict with what is on USB in.
+    ///
+    /// The interlock is checked here against the charger's current status, [`Vbus::update`] keeps checking it.
+    pub async fn set_mode(&mut self, mode: VbusMode) -> Result<(), VbusError<E, PinError<I2C>>> {
+        if self.applied && mode == self.mode {
+            return Ok(());
+        }
+
+        match mode {
+            VbusMode::ChargeFromIn => {}
+            VbusMode::PowerOutFromBattery => {
+                if self.input_present().await? {
+                    return Err(VbusError::Interlock(VbusInterlock::InputPresent));
+                }
+            }
+            VbusMode::PassThrough => {
+                if !self.input_present().await? {
+                    return Err(VbusError::Interlock(VbusInterlock::NoInput));
+                }
+            }
+        }
+
+        self.apply(mode).await
+    }
+
+    async fn apply(&mut self, mode: VbusMode) -> Result<(), VbusError<E, PinError<I2C>>> {
+        info!("Setting VBUS mode: {}", mode);
+        self.applied = false;
+
+        // Always stop boosting before touching the switch, so the boost output is never connected to a live USB in
+        self.bq
+            .reg_03()
+            .modify_async(|r| r.set_otc_config(bq25895::BoostMode::Disabled))
+            .await
+            .map_err(VbusError::I2c)?;
+
+        // Boost is now off, which is the closest description of the hardware should any of the following fail
+        self.mode = VbusMode::ChargeFromIn;
+
+        self.sw
+            .set_state(match mode.vbus_switch_closed() {
+                true => PinState::High,
+                false => PinState::Low,
+            })
+            .await
+            .map_err(VbusError::Pin)?;
+
+        self.usb
+            .set(mode.usb_port())
+            .await
+            .map_err(VbusError::Pin)?;
+
+        // The charger cannot charge and boost at the same time
+        self.bq
+            .reg_03()
+            .modify_async(|r| {
+                r.set_chg_config(match mode.boost() {
+                    true => bq25895::ChargeEnable::Disabled,
+                    false => bq25895::ChargeEnable::Enabled,
+                })
+            })
+            .await
+            .map_err(VbusError::I2c)?;
+
+        if mode.boost() {
+            self.bq
+                .reg_03()
+                .modify_async(|r| r.set_otc_config(bq25895::BoostMode::Enabled))
+                .await
+                .map_err(VbusError::I2c)?;
+        }
+
+        self.mode = mode;
+        self.applied = true;
+        Ok(())
+    }
+}
+
+fn input_present(vbus_stat: bq25895::VbusStatus, pg_stat: bq25895::PowerGood) -> bool {
+    vbus_stat != bq25895::VbusStatus::NoInput
+        && vbus_stat != bq25895::VbusStatus::Otg
+        && pg_stat == bq25895::PowerGood::Good
+}
@@ -1,3 +1,6 @@
+use defmt::Format;
+use esp_hal::gpio::AnyPin;
+
 esp_hal::assign_resources! {
     pub Resources<'d> {
         i2c: I2cResources<'d> {
@@ -12,6 +15,11 @@ esp_hal::assign_resources! {
         led: LedResources<'d> {
             data: GPIO21,
         },
+        usb: UsbResources<'d> {
+            dp: GPIO20,
+            dm: GPIO19,
+            usb: USB0,
+        },
         front_board: FrontBoardResources<'d> {
             hs_1: GPIO8,
             hs_2: GPIO7,
@@ -56,3 +64,57 @@ esp_hal::assign_resources! {
         },
     }
 }
+
+/// High speed pins of a hexpansion port, whichever port that is.
+pub struct HexpansionHsPins<'d> {
+    pub hs_1: AnyPin<'d>,
+    pub hs_2: AnyPin<'d>,
+    pub hs_3: AnyPin<'d>,
+    pub hs_4: AnyPin<'d>,
+}
+
+#[derive(Debug, Format, PartialEq, Eq, Clone, Copy)]
+pub enum HsPin {
+    Hs1,
+    Hs2,
+    Hs3,
+    Hs4,
+}
+
+impl<'d> HexpansionHsPins<'d> {
+    /// Take one of the pins, releasing the others.
+    pub fn take(self, pin: HsPin) -> AnyPin<'d> {
+        match pin {
+            HsPin::Hs1 => self.hs_1,
+            HsPin::Hs2 => self.hs_2,
+            HsPin::Hs3 => self.hs_3,
+            HsPin::Hs4 => self.hs_4,
+        }
+    }
+}
+
+macro_rules! impl_hexpansion_hs_pins {
+    ($($resources:ident),*) => {
+        $(
+            impl<'d> From<$resources<'d>> for HexpansionHsPins<'d> {
+                fn from(r: $resources<'d>) -> Self {
+                    Self {
+                        hs_1: r.hs_1.into(),
+                        hs_2: r.hs_2.into(),
+                        hs_3: r.hs_3.into(),
+                        hs_4: r.hs_4.into(),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_hexpansion_hs_pins!(
+    HexpansionAResources,
+    HexpansionBResources,
+    HexpansionCResources,
+    HexpansionDResources,
+    HexpansionEResources,
+    HexpansionFResources
+);
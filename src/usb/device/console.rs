@@ -0,0 +1,59 @@
+use super::UsbDriver;
+use embassy_usb::{
+    Builder,
+    class::cdc_acm::{CdcAcmClass, State},
+    driver::EndpointError,
+};
+
+const MAX_PACKET_SIZE: u16 = 64;
+
+/// A CDC-ACM serial port.
+pub struct Console {
+    class: CdcAcmClass<'static, UsbDriver>,
+}
+
+impl Console {
+    pub fn new(
+        builder: &mut Builder<'static, UsbDriver>,
+        state: &'static mut State<'static>,
+    ) -> Self {
+        Self {
+            class: CdcAcmClass::new(builder, state, MAX_PACKET_SIZE),
+        }
+    }
+
+    /// Wait for a terminal to open the port (i.e. assert DTR).
+    pub async fn wait_connection(&mut self) {
+        self.class.wait_connection().await
+    }
+
+    pub fn connected(&self) -> bool {
+        self.class.dtr()
+    }
+
+    /// Write all of `data`, split into packets.
+    pub async fn write_all(&mut self, data: &[u8]) -> Result<(), EndpointError> {
+        let max = self.class.max_packet_size() as usize;
+
+        for chunk in data.chunks(max) {
+            self.class.write_packet(chunk).await?;
+        }
+
+        // A full final packet must be followed by an empty one to end the transfer
+        if !data.is_empty() && data.len() % max == 0 {
+            self.class.write_packet(&[]).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a packet of received data into `buffer`, which should be at least 64 bytes.
+    pub async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, EndpointError> {
+        self.class.read_packet(buffer).await
+    }
+
+    /// Access the underlying class, e.g. to split it into separate sender and receiver.
+    pub fn into_inner(self) -> CdcAcmClass<'static, UsbDriver> {
+        self.class
+    }
+}
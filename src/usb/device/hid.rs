@@ -0,0 +1,221 @@
+use super::UsbDriver;
+use defmt::Format;
+use embassy_usb::{
+    Builder,
+    class::hid::{
+        Config, HidBootProtocol, HidReader, HidReaderWriter, HidSubclass, HidWriter, ReadError,
+        State,
+    },
+    driver::EndpointError,
+};
+
+/// Boot protocol keyboard, with a LED output report
+#[rustfmt::skip]
+const KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x06, // Usage (Keyboard)
+    0xA1, 0x01, // Collection (Application)
+    0x05, 0x07, //   Usage Page (Key Codes)
+    0x19, 0xE0, //   Usage Minimum (224)
+    0x29, 0xE7, //   Usage Maximum (231)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x08, //   Report Count (8)
+    0x81, 0x02, //   Input (Data, Variable, Absolute), modifiers
+    0x95, 0x01, //   Report Count (1)
+    0x75, 0x08, //   Report Size (8)
+    0x81, 0x01, //   Input (Constant), reserved
+    0x95, 0x05, //   Report Count (5)
+    0x75, 0x01, //   Report Size (1)
+    0x05, 0x08, //   Usage Page (LEDs)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x05, //   Usage Maximum (5)
+    0x91, 0x02, //   Output (Data, Variable, Absolute), LEDs
+    0x95, 0x01, //   Report Count (1)
+    0x75, 0x03, //   Report Size (3)
+    0x91, 0x01, //   Output (Constant), padding
+    0x95, 0x06, //   Report Count (6)
+    0x75, 0x08, //   Report Size (8)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0xFF, //   Logical Maximum (255)
+    0x05, 0x07, //   Usage Page (Key Codes)
+    0x19, 0x00, //   Usage Minimum (0)
+    0x29, 0xFF, //   Usage Maximum (255)
+    0x81, 0x00, //   Input (Data, Array), keys
+    0xC0,       // End Collection
+];
+
+/// 16 buttons and two 8 bit axes
+#[rustfmt::skip]
+const GAMEPAD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Game Pad)
+    0xA1, 0x01, // Collection (Application)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x10, //   Usage Maximum (16)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x10, //   Report Count (16)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x15, 0x81, //   Logical Minimum (-127)
+    0x25, 0x7F, //   Logical Maximum (127)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x02, //   Report Count (2)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0xC0,       // End Collection
+];
+
+pub const KEYBOARD_REPORT_SIZE: usize = 8;
+pub const GAMEPAD_REPORT_SIZE: usize = 4;
+
+/// Keyboard usage IDs (from the HID usage tables) of the keys used by [`DEFAULT_LAYER`](super::DEFAULT_LAYER).
+pub mod key {
+    pub const ENTER: u8 = 0x28;
+    pub const ESCAPE: u8 = 0x29;
+    pub const RIGHT: u8 = 0x4F;
+    pub const LEFT: u8 = 0x50;
+    pub const DOWN: u8 = 0x51;
+    pub const UP: u8 = 0x52;
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyboardReport {
+    /// Bit mask of held modifier keys, left control is bit 0
+    pub modifiers: u8,
+
+    /// Held keys, unused slots are 0
+    pub keys: [u8; 6],
+}
+
+impl KeyboardReport {
+    /// Add a key to the report, returns false if six keys are already held.
+    pub fn press(&mut self, key: u8) -> bool {
+        if self.keys.contains(&key) {
+            return true;
+        }
+        match self.keys.iter_mut().find(|k| **k == 0) {
+            Some(slot) => {
+                *slot = key;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEYBOARD_REPORT_SIZE] {
+        let mut report = [0u8; KEYBOARD_REPORT_SIZE];
+        report[0] = self.modifiers;
+        report[2..].copy_from_slice(&self.keys);
+        report
+    }
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GamepadReport {
+    /// Bit mask of pressed buttons, button 1 is bit 0
+    pub buttons: u16,
+    pub x: i8,
+    pub y: i8,
+}
+
+impl GamepadReport {
+    pub fn to_bytes(&self) -> [u8; GAMEPAD_REPORT_SIZE] {
+        let buttons = self.buttons.to_le_bytes();
+        [buttons[0], buttons[1], self.x as u8, self.y as u8]
+    }
+}
+
+/// Keyboard lock LEDs, as set by the host.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyboardLeds(pub u8);
+
+impl KeyboardLeds {
+    pub fn num_lock(&self) -> bool {
+        self.0 & 0x01 != 0
+    }
+
+    pub fn caps_lock(&self) -> bool {
+        self.0 & 0x02 != 0
+    }
+
+    pub fn scroll_lock(&self) -> bool {
+        self.0 & 0x04 != 0
+    }
+}
+
+pub type KeyboardWriter = HidWriter<'static, UsbDriver, KEYBOARD_REPORT_SIZE>;
+pub type KeyboardReader = HidReader<'static, UsbDriver, 1>;
+
+/// A boot protocol keyboard.
+pub struct HidKeyboard {
+    writer: KeyboardWriter,
+    reader: KeyboardReader,
+}
+
+impl HidKeyboard {
+    pub fn new(
+        builder: &mut Builder<'static, UsbDriver>,
+        state: &'static mut State<'static>,
+    ) -> Self {
+        let config = Config {
+            report_descriptor: KEYBOARD_REPORT_DESCRIPTOR,
+            request_handler: None,
+            poll_ms: 10,
+            max_packet_size: KEYBOARD_REPORT_SIZE as u16,
+            hid_subclass: HidSubclass::Boot,
+            hid_boot_protocol: HidBootProtocol::Keyboard,
+        };
+        let (reader, writer) = HidReaderWriter::new(builder, state, config).split();
+        Self { writer, reader }
+    }
+
+    pub async fn send(&mut self, report: &KeyboardReport) -> Result<(), EndpointError> {
+        self.writer.write(&report.to_bytes()).await
+    }
+
+    /// Wait for the host to change the lock LEDs.
+    pub async fn read_leds(&mut self) -> Result<KeyboardLeds, ReadError> {
+        let mut report = [0u8; 1];
+        self.reader.read(&mut report).await?;
+        Ok(KeyboardLeds(report[0]))
+    }
+
+    /// Separate halves, so that reports can be sent whilst waiting for LED changes.
+    pub fn split(&mut self) -> (&mut KeyboardWriter, &mut KeyboardReader) {
+        (&mut self.writer, &mut self.reader)
+    }
+}
+
+/// A gamepad with 16 buttons and an X/Y stick.
+pub struct HidGamepad {
+    writer: HidWriter<'static, UsbDriver, GAMEPAD_REPORT_SIZE>,
+}
+
+impl HidGamepad {
+    pub fn new(
+        builder: &mut Builder<'static, UsbDriver>,
+        state: &'static mut State<'static>,
+    ) -> Self {
+        let config = Config {
+            report_descriptor: GAMEPAD_REPORT_DESCRIPTOR,
+            request_handler: None,
+            poll_ms: 10,
+            max_packet_size: GAMEPAD_REPORT_SIZE as u16,
+            hid_subclass: HidSubclass::No,
+            hid_boot_protocol: HidBootProtocol::None,
+        };
+        Self {
+            writer: HidWriter::new(builder, state, config),
+        }
+    }
+
+    pub async fn send(&mut self, report: &GamepadReport) -> Result<(), EndpointError> {
+        self.writer.write(&report.to_bytes()).await
+    }
+}
@@ -0,0 +1,331 @@
+//! Configurable mapping of button presses (and optionally tilt) to keyboard and gamepad reports, for using the badge as
+//! a macro pad or game controller.
+
+use super::{GamepadReport, KeyboardLeds, KeyboardReport, key};
+use crate::{
+    button_collection::ButtonEvent,
+    front::{FrontBoardLedGeometry, emf2024::SystemButton, leds::FrontLeds},
+    imu::Attitude,
+};
+use defmt::{Format, debug};
+use micromath::F32Ext;
+use smart_leds::RGB8;
+use strum::EnumCount;
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Do nothing
+    None,
+
+    /// Use the action of the next lower layer
+    Transparent,
+
+    /// A key, as a usage ID from the HID keyboard page
+    Key(u8),
+
+    /// A key with modifiers held, e.g. for shortcuts
+    ModifiedKey { modifiers: u8, key: u8 },
+
+    /// Modifier keys, as a bit mask (left control is bit 0)
+    Modifier(u8),
+
+    /// A gamepad button, from 0
+    GamepadButton(u8),
+
+    /// Switch to a layer while held
+    LayerHold(u8),
+
+    /// Switch to a layer, or back to layer 0 if already on it
+    LayerToggle(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layer {
+    /// Actions for buttons A to F
+    pub actions: [KeyAction; SystemButton::COUNT],
+
+    /// Shown on the LED ring while the layer is active
+    pub colour: RGB8,
+}
+
+/// Arrow keys around the ring, with enter and escape.
+pub const DEFAULT_LAYER: Layer = Layer {
+    actions: [
+        KeyAction::Key(key::UP),
+        KeyAction::Key(key::RIGHT),
+        KeyAction::Key(key::ENTER),
+        KeyAction::Key(key::DOWN),
+        KeyAction::Key(key::ESCAPE),
+        KeyAction::Key(key::LEFT),
+    ],
+    colour: RGB8::new(0, 0, 32),
+};
+
+/// Buttons A to F as gamepad buttons 0 to 5.
+pub const GAMEPAD_LAYER: Layer = Layer {
+    actions: [
+        KeyAction::GamepadButton(0),
+        KeyAction::GamepadButton(1),
+        KeyAction::GamepadButton(2),
+        KeyAction::GamepadButton(3),
+        KeyAction::GamepadButton(4),
+        KeyAction::GamepadButton(5),
+    ],
+    colour: RGB8::new(0, 32, 0),
+};
+
+/// An action for pressing several buttons together.
+///
+/// The chord is recognised when the last of its buttons is pressed, the actions of the buttons pressed before it will
+/// already have been sent. Map them to [`KeyAction::None`] (or something harmless) where that matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    pub buttons: &'static [SystemButton],
+    pub action: KeyAction,
+}
+
+impl Chord {
+    fn mask(&self) -> u8 {
+        self.buttons.iter().fold(0, |mask, b| mask | button_bit(*b))
+    }
+}
+
+/// Tilt of the badge reported as the gamepad X/Y axes.
+#[derive(Debug, Format, Clone, Copy, PartialEq)]
+pub struct TiltConfig {
+    /// Tilt giving full deflection, in degrees
+    pub full_scale: f32,
+
+    /// Tilt ignored around level, in degrees
+    pub deadzone: f32,
+}
+
+impl Default for TiltConfig {
+    fn default() -> Self {
+        Self {
+            full_scale: 30.0,
+            deadzone: 3.0,
+        }
+    }
+}
+
+pub struct HidMappingConfig {
+    /// Layer 0 is the base layer, at least one is required
+    pub layers: &'static [Layer],
+
+    pub chords: &'static [Chord],
+
+    pub tilt: Option<TiltConfig>,
+
+    /// Shown at the top of the LED ring while caps lock is on
+    pub caps_lock_colour: RGB8,
+}
+
+impl Default for HidMappingConfig {
+    /// [`DEFAULT_LAYER`] only, without chords or tilt.
+    fn default() -> Self {
+        Self {
+            layers: &[DEFAULT_LAYER],
+            chords: &[],
+            tilt: None,
+            caps_lock_colour: RGB8::new(255, 192, 0),
+        }
+    }
+}
+
+/// Converts button events into HID reports.
+pub struct HidMapping {
+    config: HidMappingConfig,
+    held: u8,
+    actions: [KeyAction; SystemButton::COUNT],
+    toggled_layer: u8,
+    axes: (i8, i8),
+    leds: KeyboardLeds,
+    dirty: bool,
+}
+
+impl HidMapping {
+    pub fn new(config: HidMappingConfig) -> Self {
+        Self {
+            config,
+            held: 0,
+            actions: [KeyAction::None; SystemButton::COUNT],
+            toggled_layer: 0,
+            axes: (0, 0),
+            leds: KeyboardLeds::default(),
+            dirty: true,
+        }
+    }
+
+    /// The layer currently in use.
+    pub fn layer(&self) -> u8 {
+        self.actions
+            .iter()
+            .filter_map(|action| match action {
+                KeyAction::LayerHold(layer) => Some(*layer),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(self.toggled_layer)
+    }
+
+    /// Update from a button event, returns true if the reports may have changed.
+    pub fn handle_button_event(&mut self, event: &ButtonEvent<SystemButton>) -> bool {
+        let button = *event.button();
+        let bit = button_bit(button);
+        let previous_layer = self.layer();
+
+        if event.pressed() {
+            if self.held & bit != 0 {
+                return false;
+            }
+            self.held |= bit;
+
+            let chord = self
+                .config
+                .chords
+                .iter()
+                .find(|chord| chord.mask() & bit != 0 && chord.mask() == self.held);
+
+            let action = match chord {
+                Some(chord) => {
+                    // The chord replaces what the other buttons in it were doing
+                    for other in chord.buttons {
+                        self.actions[*other as usize] = KeyAction::None;
+                    }
+                    chord.action
+                }
+                None => self.resolve(button, previous_layer),
+            };
+
+            if let KeyAction::LayerToggle(layer) = action {
+                self.toggled_layer = match self.toggled_layer == layer {
+                    true => 0,
+                    false => layer,
+                };
+            }
+
+            debug!("HID {} pressed: {}", button, action);
+            self.actions[button as usize] = action;
+        } else {
+            if self.held & bit == 0 {
+                return false;
+            }
+            self.held &= !bit;
+            self.actions[button as usize] = KeyAction::None;
+        }
+
+        if self.layer() != previous_layer {
+            self.dirty = true;
+        }
+        true
+    }
+
+    /// Update the gamepad axes from the badge attitude, returns true if they changed.
+    ///
+    /// Does nothing unless [`HidMappingConfig::tilt`] is set.
+    pub fn handle_attitude(&mut self, attitude: &Attitude) -> bool {
+        let Some(tilt) = self.config.tilt else {
+            return false;
+        };
+
+        let axis = |angle: f32| {
+            let magnitude = angle.abs() - tilt.deadzone;
+            if magnitude <= 0.0 {
+                return 0;
+            }
+            let scaled = magnitude / (tilt.full_scale - tilt.deadzone).max(1.0) * 127.0;
+            (scaled.min(127.0) * angle.signum()) as i8
+        };
+
+        let axes = (axis(attitude.roll), axis(attitude.pitch));
+        let changed = axes != self.axes;
+        self.axes = axes;
+        changed
+    }
+
+    /// Record the lock LEDs set by the host, for display with [`HidMapping::render`].
+    pub fn handle_keyboard_leds(&mut self, leds: KeyboardLeds) {
+        if leds != self.leds {
+            self.leds = leds;
+            self.dirty = true;
+        }
+    }
+
+    pub fn keyboard_report(&self) -> KeyboardReport {
+        let mut report = KeyboardReport::default();
+        for action in self.actions {
+            match action {
+                KeyAction::Key(key) => {
+                    report.press(key);
+                }
+                KeyAction::ModifiedKey { modifiers, key } => {
+                    report.modifiers |= modifiers;
+                    report.press(key);
+                }
+                KeyAction::Modifier(modifiers) => report.modifiers |= modifiers,
+                _ => {}
+            }
+        }
+        report
+    }
+
+    pub fn gamepad_report(&self) -> GamepadReport {
+        let mut report = GamepadReport {
+            buttons: 0,
+            x: self.axes.0,
+            y: self.axes.1,
+        };
+        for action in self.actions {
+            if let KeyAction::GamepadButton(button) = action {
+                report.buttons |= 1u16.checked_shl(button as u32).unwrap_or(0);
+            }
+        }
+        report
+    }
+
+    /// Show the layer colour on the front LED ring, and caps lock at the top, if anything has changed since the last
+    /// render.
+    ///
+    /// Returns true if the LEDs were changed.
+    pub fn render<G: FrontBoardLedGeometry>(&mut self, buffer: &mut impl FrontLeds) -> bool {
+        if !self.dirty {
+            return false;
+        }
+
+        let colour = self
+            .config
+            .layers
+            .get(self.layer() as usize)
+            .map(|layer| layer.colour)
+            .unwrap_or_default();
+        let leds = buffer.front();
+        leds.fill(colour);
+
+        if self.leds.caps_lock() {
+            if let Some(led) = G::front_closest_to_angle(0).and_then(|i| leds.get_mut(i)) {
+                *led = self.config.caps_lock_colour;
+            }
+        }
+
+        self.dirty = false;
+        true
+    }
+
+    /// Action of a button on a layer, falling through transparent actions.
+    fn resolve(&self, button: SystemButton, layer: u8) -> KeyAction {
+        let layers = self.config.layers;
+        let top = (layer as usize + 1).min(layers.len());
+
+        layers[..top]
+            .iter()
+            .rev()
+            .map(|layer| layer.actions[button as usize])
+            .find(|action| *action != KeyAction::Transparent)
+            .unwrap_or(KeyAction::None)
+    }
+}
+
+fn button_bit(button: SystemButton) -> u8 {
+    1 << (button as u8)
+}
@@ -0,0 +1,150 @@
+//! USB device support using [`embassy_usb`] on the ESP32-S3 OTG peripheral.
+//!
+//! Create a [`Builder`] with [`builder`], add the classes needed ([`Console`], [`HidKeyboard`], [`HidGamepad`],
+//! [`MassStorage`], e.g. of a [`SdCardBlockDevice`]) and build the device. [`HidMapping`] turns button presses into
+//! reports for the HID classes.
+//!
+//! The data lines only reach a host when [`UsbSwitch`](super::UsbSwitch) is routed to the port it is attached to,
+//! [`run`] keeps the device connected only while that is the case.
+//!
+//! Taking over the USB peripheral disconnects the USB Serial/JTAG controller, so `espflash` monitoring and flashing
+//! over USB will no longer work once the device is started.
+
+mod console;
+mod hid;
+mod mapping;
+mod msc;
+mod sd_card;
+
+pub use console::*;
+pub use hid::*;
+pub use mapping::*;
+pub use msc::*;
+pub use sd_card::*;
+
+use crate::resources::UsbResources;
+use defmt::info;
+use embassy_futures::select::{Either, select};
+use embassy_sync::{blocking_mutex::raw::RawMutex, signal::Signal};
+use embassy_usb::{Builder, UsbDevice};
+use esp_hal::otg_fs::{
+    Usb,
+    asynch::{Config as OtgConfig, Driver},
+};
+
+pub type UsbDriver = Driver<'static>;
+
+/// Buffers needed by the USB stack, these must outlive the device so would usually be in a `static`.
+pub struct UsbBuffers {
+    config_descriptor: [u8; 256],
+    bos_descriptor: [u8; 256],
+    msos_descriptor: [u8; 256],
+    control: [u8; 64],
+    ep_out: [u8; 1024],
+}
+
+impl UsbBuffers {
+    pub const fn new() -> Self {
+        Self {
+            config_descriptor: [0; 256],
+            bos_descriptor: [0; 256],
+            msos_descriptor: [0; 256],
+            control: [0; 64],
+            ep_out: [0; 1024],
+        }
+    }
+}
+
+impl Default for UsbBuffers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsbDeviceConfig {
+    pub vid: u16,
+    pub pid: u16,
+    pub manufacturer: &'static str,
+    pub product: &'static str,
+    pub serial_number: Option<&'static str>,
+
+    /// Current drawn from the host, in mA
+    pub max_power: u16,
+}
+
+impl Default for UsbDeviceConfig {
+    fn default() -> Self {
+        Self {
+            // Espressif test VID/PID, replace with an allocated pair for anything distributed
+            vid: 0x303A,
+            pid: 0x3001,
+            manufacturer: "Electromagnetic Field",
+            product: "Tildagon",
+            serial_number: None,
+            max_power: 500,
+        }
+    }
+}
+
+/// Set up the OTG peripheral and start building a USB device.
+pub fn builder(
+    r: UsbResources<'static>,
+    buffers: &'static mut UsbBuffers,
+    config: UsbDeviceConfig,
+) -> Builder<'static, UsbDriver> {
+    let usb = Usb::new(r.usb, r.dp, r.dm);
+    let driver = Driver::new(usb, &mut buffers.ep_out, OtgConfig::default());
+
+    let mut usb_config = embassy_usb::Config::new(config.vid, config.pid);
+    usb_config.manufacturer = Some(config.manufacturer);
+    usb_config.product = Some(config.product);
+    usb_config.serial_number = config.serial_number;
+    usb_config.max_power = config.max_power;
+    usb_config.max_packet_size_0 = 64;
+
+    // Required for composite devices (e.g. console and HID together) on Windows
+    usb_config.composite_with_iads = true;
+    usb_config.device_class = 0xEF;
+    usb_config.device_sub_class = 0x02;
+    usb_config.device_protocol = 0x01;
+
+    Builder::new(
+        driver,
+        usb_config,
+        &mut buffers.config_descriptor,
+        &mut buffers.bos_descriptor,
+        &mut buffers.msos_descriptor,
+        &mut buffers.control,
+    )
+}
+
+/// Run the device whilst the data lines are routed to a host.
+///
+/// `routed` should be signalled with [`UsbManager::host_port`](super::UsbManager::host_port) being `Some` whenever it
+/// changes. The device is disconnected from the bus while it is not routed, so that a host on the other port does
+/// not see a half enumerated device when routing changes.
+pub async fn run<M: RawMutex>(
+    device: &mut UsbDevice<'static, UsbDriver>,
+    routed: &Signal<M, bool>,
+) -> ! {
+    let mut connected = false;
+
+    loop {
+        if !connected {
+            connected = routed.wait().await;
+            continue;
+        }
+
+        info!("USB device connected");
+        connected = match select(device.run(), routed.wait()).await {
+            Either::First(never) => never,
+            Either::Second(routed) => routed,
+        };
+
+        if !connected {
+            info!("USB device disconnected");
+            device.disable().await;
+        }
+    }
+}
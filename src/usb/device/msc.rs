@@ -0,0 +1,437 @@
+//! USB mass storage (bulk only transport) exposing a block device, such as a microSD card, over the SCSI transparent
+//! command set.
+
+use super::UsbDriver;
+use defmt::{Format, debug, warn};
+use embassy_usb::{
+    Builder,
+    driver::{Driver, Endpoint, EndpointError, EndpointIn, EndpointOut},
+};
+
+/// Size of a block, only 512 byte blocks are supported.
+pub const BLOCK_SIZE: usize = 512;
+
+const MAX_PACKET_SIZE: u16 = 64;
+
+const CLASS_MASS_STORAGE: u8 = 0x08;
+const SUBCLASS_SCSI: u8 = 0x06;
+const PROTOCOL_BULK_ONLY: u8 = 0x50;
+
+const CBW_SIGNATURE: u32 = 0x4342_5355;
+const CBW_SIZE: usize = 31;
+const CSW_SIGNATURE: u32 = 0x5342_5355;
+
+mod opcode {
+    pub const TEST_UNIT_READY: u8 = 0x00;
+    pub const REQUEST_SENSE: u8 = 0x03;
+    pub const INQUIRY: u8 = 0x12;
+    pub const MODE_SENSE_6: u8 = 0x1A;
+    pub const START_STOP_UNIT: u8 = 0x1B;
+    pub const PREVENT_ALLOW_MEDIUM_REMOVAL: u8 = 0x1E;
+    pub const READ_FORMAT_CAPACITIES: u8 = 0x23;
+    pub const READ_CAPACITY_10: u8 = 0x25;
+    pub const READ_10: u8 = 0x28;
+    pub const WRITE_10: u8 = 0x2A;
+    pub const VERIFY_10: u8 = 0x2F;
+    pub const SYNCHRONIZE_CACHE_10: u8 = 0x35;
+    pub const MODE_SENSE_10: u8 = 0x5A;
+}
+
+/// Storage exposed by [`MassStorage`].
+#[allow(async_fn_in_trait)]
+pub trait BlockDevice {
+    type Error;
+
+    /// Number of blocks, 0 if no medium is present (e.g. the card has been removed).
+    fn block_count(&mut self) -> u32;
+
+    fn read_only(&self) -> bool {
+        false
+    }
+
+    async fn read_block(
+        &mut self,
+        lba: u32,
+        block: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), Self::Error>;
+
+    async fn write_block(&mut self, lba: u32, block: &[u8; BLOCK_SIZE]) -> Result<(), Self::Error>;
+}
+
+/// SCSI sense data describing why the last command failed.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Default)]
+struct Sense {
+    key: u8,
+    asc: u8,
+    ascq: u8,
+}
+
+impl Sense {
+    const NONE: Self = Self::new(0x00, 0x00, 0x00);
+    const NOT_PRESENT: Self = Self::new(0x02, 0x3A, 0x00);
+    const READ_ERROR: Self = Self::new(0x03, 0x11, 0x00);
+    const WRITE_ERROR: Self = Self::new(0x03, 0x0C, 0x00);
+    const INVALID_COMMAND: Self = Self::new(0x05, 0x20, 0x00);
+    const OUT_OF_RANGE: Self = Self::new(0x05, 0x21, 0x00);
+    const WRITE_PROTECTED: Self = Self::new(0x07, 0x27, 0x00);
+
+    const fn new(key: u8, asc: u8, ascq: u8) -> Self {
+        Self { key, asc, ascq }
+    }
+}
+
+struct CommandBlock {
+    tag: u32,
+    data_length: u32,
+    data_in: bool,
+    cb: [u8; 16],
+}
+
+impl CommandBlock {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != CBW_SIZE
+            || u32::from_le_bytes([data[0], data[1], data[2], data[3]]) != CBW_SIGNATURE
+        {
+            return None;
+        }
+
+        let mut cb = [0u8; 16];
+        let cb_length = (data[14] as usize).min(16);
+        cb[..cb_length].copy_from_slice(&data[15..15 + cb_length]);
+
+        Some(Self {
+            tag: u32::from_le_bytes([data[4], data[5], data[6], data[7]]),
+            data_length: u32::from_le_bytes([data[8], data[9], data[10], data[11]]),
+            data_in: data[12] & 0x80 != 0,
+            cb,
+        })
+    }
+
+    /// Logical block address and block count of a 10 byte read/write/verify command.
+    fn blocks(&self) -> (u32, u32) {
+        let cb = &self.cb;
+        (
+            u32::from_be_bytes([cb[2], cb[3], cb[4], cb[5]]),
+            u16::from_be_bytes([cb[7], cb[8]]) as u32,
+        )
+    }
+}
+
+enum Status {
+    Passed,
+    Failed(Sense),
+}
+
+type EpIn = <UsbDriver as Driver<'static>>::EndpointIn;
+type EpOut = <UsbDriver as Driver<'static>>::EndpointOut;
+
+pub struct MassStorage<B> {
+    device: B,
+    ep_in: EpIn,
+    ep_out: EpOut,
+    sense: Sense,
+
+    /// The host ended the data phase of the current command early with a short packet
+    data_out_ended: bool,
+
+    vendor: [u8; 8],
+    product: [u8; 16],
+}
+
+impl<B: BlockDevice> MassStorage<B> {
+    /// `vendor` and `product` are shown by the host, truncated to 8 and 16 characters.
+    pub fn new(
+        builder: &mut Builder<'static, UsbDriver>,
+        device: B,
+        vendor: &str,
+        product: &str,
+    ) -> Self {
+        let mut function = builder.function(CLASS_MASS_STORAGE, SUBCLASS_SCSI, PROTOCOL_BULK_ONLY);
+        let mut interface = function.interface();
+        let mut alt =
+            interface.alt_setting(CLASS_MASS_STORAGE, SUBCLASS_SCSI, PROTOCOL_BULK_ONLY, None);
+        let ep_out = alt.endpoint_bulk_out(None, MAX_PACKET_SIZE);
+        let ep_in = alt.endpoint_bulk_in(None, MAX_PACKET_SIZE);
+
+        Self {
+            device,
+            ep_in,
+            ep_out,
+            sense: Sense::NONE,
+            data_out_ended: false,
+            vendor: padded(vendor),
+            product: padded(product),
+        }
+    }
+
+    pub fn device(&mut self) -> &mut B {
+        &mut self.device
+    }
+
+    /// Handle commands from the host, this never returns.
+    pub async fn run(&mut self) -> ! {
+        loop {
+            self.ep_out.wait_enabled().await;
+            debug!("Mass storage enabled");
+
+            loop {
+                match self.handle_command().await {
+                    Ok(()) => {}
+                    Err(EndpointError::Disabled) => break,
+                    Err(EndpointError::BufferOverflow) => warn!("Mass storage buffer overflow"),
+                }
+            }
+        }
+    }
+
+    async fn handle_command(&mut self) -> Result<(), EndpointError> {
+        let mut packet = [0u8; MAX_PACKET_SIZE as usize];
+        let len = self.ep_out.read(&mut packet).await?;
+
+        // Stalling is not possible here, so invalid commands are ignored and the host will reset
+        let Some(command) = CommandBlock::parse(&packet[..len]) else {
+            warn!("Invalid mass storage command block");
+            return Ok(());
+        };
+
+        self.data_out_ended = false;
+        let (status, transferred) = self.execute(&command).await?;
+
+        // Data the host expected to send but was not used must still be taken
+        let mut transferred = transferred;
+        if command.data_in {
+            // End a short response that would otherwise look incomplete
+            if transferred < command.data_length && transferred % MAX_PACKET_SIZE as u32 == 0 {
+                self.ep_in.write(&[]).await?;
+            }
+        } else {
+            while !self.data_out_ended && transferred < command.data_length {
+                let len = self.ep_out.read(&mut packet).await?;
+                transferred += len as u32;
+                self.data_out_ended = len < MAX_PACKET_SIZE as usize;
+            }
+        }
+
+        let status = match status {
+            Status::Passed => {
+                self.sense = Sense::NONE;
+                0
+            }
+            Status::Failed(sense) => {
+                debug!("SCSI command {:#x} failed: {}", command.cb[0], sense);
+                self.sense = sense;
+                1
+            }
+        };
+
+        let mut csw = [0u8; 13];
+        csw[..4].copy_from_slice(&CSW_SIGNATURE.to_le_bytes());
+        csw[4..8].copy_from_slice(&command.tag.to_le_bytes());
+        csw[8..12].copy_from_slice(
+            &command
+                .data_length
+                .saturating_sub(transferred)
+                .to_le_bytes(),
+        );
+        csw[12] = status;
+        self.ep_in.write(&csw).await
+    }
+
+    /// Run a command, returning its status and the number of data bytes transferred.
+    async fn execute(&mut self, command: &CommandBlock) -> Result<(Status, u32), EndpointError> {
+        let block_count = self.device.block_count();
+        let present = block_count > 0;
+        let limit = command.data_length as usize;
+
+        match command.cb[0] {
+            opcode::TEST_UNIT_READY => Ok((ready(present), 0)),
+
+            opcode::REQUEST_SENSE => {
+                let mut sense = [0u8; 18];
+                sense[0] = 0x70;
+                sense[2] = self.sense.key;
+                sense[7] = 10;
+                sense[12] = self.sense.asc;
+                sense[13] = self.sense.ascq;
+                let sent = self.write_data(&sense, limit).await?;
+                Ok((Status::Passed, sent))
+            }
+
+            opcode::INQUIRY => {
+                let mut inquiry = [0u8; 36];
+                // Direct access, removable, SPC-2
+                inquiry[1] = 0x80;
+                inquiry[2] = 0x04;
+                inquiry[3] = 0x02;
+                inquiry[4] = 31;
+                inquiry[8..16].copy_from_slice(&self.vendor);
+                inquiry[16..32].copy_from_slice(&self.product);
+                inquiry[32..36].copy_from_slice(b"1.00");
+                let sent = self.write_data(&inquiry, limit).await?;
+                Ok((Status::Passed, sent))
+            }
+
+            // Header only, no block descriptors or mode pages
+            opcode::MODE_SENSE_6 => {
+                let sent = self
+                    .write_data(&[3, 0, self.write_protect(), 0], limit)
+                    .await?;
+                Ok((Status::Passed, sent))
+            }
+
+            opcode::MODE_SENSE_10 => {
+                let sent = self
+                    .write_data(&[0, 6, 0, self.write_protect(), 0, 0, 0, 0], limit)
+                    .await?;
+                Ok((Status::Passed, sent))
+            }
+
+            opcode::START_STOP_UNIT
+            | opcode::PREVENT_ALLOW_MEDIUM_REMOVAL
+            | opcode::SYNCHRONIZE_CACHE_10 => Ok((Status::Passed, 0)),
+
+            opcode::VERIFY_10 => Ok((ready(present), 0)),
+
+            opcode::READ_FORMAT_CAPACITIES => {
+                let mut capacities = [0u8; 12];
+                capacities[3] = 8;
+                capacities[4..8].copy_from_slice(&block_count.to_be_bytes());
+                // Formatted media, or no media present
+                capacities[8] = if present { 0x02 } else { 0x03 };
+                capacities[9..12].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes()[1..]);
+                let sent = self.write_data(&capacities, limit).await?;
+                Ok((Status::Passed, sent))
+            }
+
+            opcode::READ_CAPACITY_10 => {
+                if !present {
+                    return Ok((Status::Failed(Sense::NOT_PRESENT), 0));
+                }
+                let mut capacity = [0u8; 8];
+                capacity[..4].copy_from_slice(&(block_count - 1).to_be_bytes());
+                capacity[4..].copy_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+                let sent = self.write_data(&capacity, limit).await?;
+                Ok((Status::Passed, sent))
+            }
+
+            opcode::READ_10 => {
+                let (lba, count) = command.blocks();
+                if !present {
+                    return Ok((Status::Failed(Sense::NOT_PRESENT), 0));
+                }
+                if !command.data_in || lba.saturating_add(count) > block_count {
+                    return Ok((Status::Failed(Sense::OUT_OF_RANGE), 0));
+                }
+                self.read_blocks(lba, count, command.data_length).await
+            }
+
+            opcode::WRITE_10 => {
+                let (lba, count) = command.blocks();
+                if !present {
+                    return Ok((Status::Failed(Sense::NOT_PRESENT), 0));
+                }
+                if self.device.read_only() {
+                    return Ok((Status::Failed(Sense::WRITE_PROTECTED), 0));
+                }
+                if command.data_in || lba.saturating_add(count) > block_count {
+                    return Ok((Status::Failed(Sense::OUT_OF_RANGE), 0));
+                }
+                self.write_blocks(lba, count, command.data_length).await
+            }
+
+            _ => Ok((Status::Failed(Sense::INVALID_COMMAND), 0)),
+        }
+    }
+
+    async fn read_blocks(
+        &mut self,
+        lba: u32,
+        count: u32,
+        data_length: u32,
+    ) -> Result<(Status, u32), EndpointError> {
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut sent = 0;
+
+        for lba in lba..lba + count {
+            if sent + BLOCK_SIZE as u32 > data_length {
+                break;
+            }
+            if self.device.read_block(lba, &mut block).await.is_err() {
+                return Ok((Status::Failed(Sense::READ_ERROR), sent));
+            }
+            for packet in block.chunks(MAX_PACKET_SIZE as usize) {
+                self.ep_in.write(packet).await?;
+            }
+            sent += BLOCK_SIZE as u32;
+        }
+
+        Ok((Status::Passed, sent))
+    }
+
+    async fn write_blocks(
+        &mut self,
+        lba: u32,
+        count: u32,
+        data_length: u32,
+    ) -> Result<(Status, u32), EndpointError> {
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut received = 0;
+        let mut status = Status::Passed;
+
+        for lba in lba..lba + count {
+            if received + BLOCK_SIZE as u32 > data_length {
+                break;
+            }
+            for packet in block.chunks_mut(MAX_PACKET_SIZE as usize) {
+                let len = self.ep_out.read(packet).await?;
+                received += len as u32;
+
+                // The host sent less than it said it would, the block is incomplete so must not be written
+                if len < packet.len() {
+                    self.data_out_ended = true;
+                    return Ok((Status::Failed(Sense::WRITE_ERROR), received));
+                }
+            }
+
+            // Keep taking the data after a failure so the transfer completes
+            if matches!(status, Status::Passed)
+                && self.device.write_block(lba, &block).await.is_err()
+            {
+                status = Status::Failed(Sense::WRITE_ERROR);
+            }
+        }
+
+        Ok((status, received))
+    }
+
+    /// Device specific parameter of the mode sense header.
+    fn write_protect(&self) -> u8 {
+        match self.device.read_only() {
+            true => 0x80,
+            false => 0x00,
+        }
+    }
+
+    /// Send a response, truncated to what the host asked for.
+    async fn write_data(&mut self, data: &[u8], limit: usize) -> Result<u32, EndpointError> {
+        let data = &data[..data.len().min(limit)];
+        for packet in data.chunks(MAX_PACKET_SIZE as usize) {
+            self.ep_in.write(packet).await?;
+        }
+        Ok(data.len() as u32)
+    }
+}
+
+fn ready(present: bool) -> Status {
+    match present {
+        true => Status::Passed,
+        false => Status::Failed(Sense::NOT_PRESENT),
+    }
+}
+
+fn padded<const N: usize>(s: &str) -> [u8; N] {
+    let mut padded = [b' '; N];
+    let len = s.len().min(N);
+    padded[..len].copy_from_slice(&s.as_bytes()[..len]);
+    padded
+}
@@ -0,0 +1,168 @@
+//! A microSD card on a hexpansion port, as a [`BlockDevice`] for [`MassStorage`](super::MassStorage).
+//!
+//! The card is driven over SPI on the port's high speed pins using [`embedded_sdmmc`]. Its driver is blocking, so each
+//! block transfer holds up the executor running the mass storage class.
+
+use super::{BLOCK_SIZE, BlockDevice};
+use crate::resources::{HexpansionHsPins, HsPin};
+use defmt::{Format, info, warn};
+use embedded_hal_bus::spi::ExclusiveDevice;
+use embedded_sdmmc::{Block, BlockDevice as _, BlockIdx, SdCard, SdCardError};
+use esp_hal::{
+    Blocking,
+    delay::Delay,
+    gpio::{Level, Output},
+    spi::{
+        Mode,
+        master::{Config, ConfigError, Instance, Spi},
+    },
+    time::Rate,
+};
+
+/// Which high speed pins of the hexpansion carry each SPI signal of the card.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub struct SdCardPinout {
+    pub sck: HsPin,
+    pub mosi: HsPin,
+    pub miso: HsPin,
+    pub cs: HsPin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SdCardConfig {
+    pub pinout: SdCardPinout,
+
+    /// SPI clock once the card is initialised, which itself is always done at 400 kHz
+    pub spi_frequency: Rate,
+}
+
+#[derive(Debug, Format)]
+pub enum SdCardBlockDeviceError {
+    SpiConfig(ConfigError),
+
+    /// The same high speed pin was given for more than one signal
+    InvalidPinout,
+}
+
+type Card<'d> = SdCard<ExclusiveDevice<Spi<'d, Blocking>, Output<'d>, Delay>, Delay>;
+
+pub struct SdCardBlockDevice<'d> {
+    card: Card<'d>,
+    spi_frequency: Rate,
+
+    /// Size of the card, `None` until it has been initialised
+    blocks: Option<u32>,
+}
+
+impl<'d> SdCardBlockDevice<'d> {
+    /// `pins` would usually be converted from the port's resources (e.g.
+    /// [`HexpansionAResources`](crate::resources::HexpansionAResources)).
+    ///
+    /// The card is not accessed until the host first asks about it, so it may be inserted later.
+    pub fn on_port(
+        spi: impl Instance + 'd,
+        pins: HexpansionHsPins<'d>,
+        config: SdCardConfig,
+    ) -> Result<Self, SdCardBlockDeviceError> {
+        let mut pins = [
+            Some(pins.hs_1),
+            Some(pins.hs_2),
+            Some(pins.hs_3),
+            Some(pins.hs_4),
+        ];
+        let mut take = |pin: HsPin| {
+            pins[pin as usize]
+                .take()
+                .ok_or(SdCardBlockDeviceError::InvalidPinout)
+        };
+        let pinout = config.pinout;
+        let (sck, mosi, miso, cs) = (
+            take(pinout.sck)?,
+            take(pinout.mosi)?,
+            take(pinout.miso)?,
+            take(pinout.cs)?,
+        );
+
+        let spi = Spi::new(spi, spi_config(Rate::from_khz(400)))
+            .map_err(SdCardBlockDeviceError::SpiConfig)?
+            .with_sck(sck)
+            .with_mosi(mosi)
+            .with_miso(miso);
+
+        let cs = Output::new(cs, Level::High, Default::default());
+        let Ok(dev) = ExclusiveDevice::new(spi, cs, Delay::new());
+
+        Ok(Self {
+            card: SdCard::new(dev, Delay::new()),
+            spi_frequency: config.spi_frequency,
+            blocks: None,
+        })
+    }
+
+    /// Initialise the card if that has not been done since it was inserted, returning its size.
+    fn acquire(&mut self) -> Result<u32, SdCardError> {
+        if let Some(blocks) = self.blocks {
+            return Ok(blocks);
+        }
+
+        let blocks = self.card.num_blocks()?.0;
+        info!("SD card with {} blocks", blocks);
+
+        let frequency = self.spi_frequency;
+        self.card.spi(|dev| {
+            if dev.bus_mut().apply_config(&spi_config(frequency)).is_err() {
+                warn!("Failed to raise SD card SPI clock");
+            }
+        });
+
+        self.blocks = Some(blocks);
+        Ok(blocks)
+    }
+
+    /// Forget the card after an error, so that a replacement is initialised.
+    fn release<T>(&mut self, result: Result<T, SdCardError>) -> Result<T, SdCardError> {
+        if let Err(e) = &result {
+            warn!("SD card error: {}", e);
+            self.blocks = None;
+            self.card.mark_card_uninit();
+            self.card.spi(|dev| {
+                let _ = dev.bus_mut().apply_config(&spi_config(Rate::from_khz(400)));
+            });
+        }
+        result
+    }
+}
+
+impl BlockDevice for SdCardBlockDevice<'_> {
+    type Error = SdCardError;
+
+    fn block_count(&mut self) -> u32 {
+        let result = self.acquire();
+        self.release(result).unwrap_or(0)
+    }
+
+    async fn read_block(
+        &mut self,
+        lba: u32,
+        block: &mut [u8; BLOCK_SIZE],
+    ) -> Result<(), Self::Error> {
+        let mut blocks = [Block::new()];
+        let result = self.card.read(&mut blocks, BlockIdx(lba));
+        self.release(result)?;
+        block.copy_from_slice(&blocks[0].contents);
+        Ok(())
+    }
+
+    async fn write_block(&mut self, lba: u32, block: &[u8; BLOCK_SIZE]) -> Result<(), Self::Error> {
+        let mut blocks = [Block::new()];
+        blocks[0].contents.copy_from_slice(block);
+        let result = self.card.write(&blocks, BlockIdx(lba));
+        self.release(result)
+    }
+}
+
+fn spi_config(frequency: Rate) -> Config {
+    Config::default()
+        .with_frequency(frequency)
+        .with_mode(Mode::_0)
+}
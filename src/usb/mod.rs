@@ -0,0 +1,233 @@
+#[cfg(feature = "usb-device")]
+pub mod device;
+
+use crate::{
+    pins::{LockedOutput, PinError, UsbPins},
+    power::{Vbus, VbusError, VbusMode},
+};
+use bq25895::{Bq25895, Interface};
+use defmt::{Format, debug, info, warn};
+use embassy_sync::{blocking_mutex::raw::RawMutex, channel::Sender};
+use embassy_time::Instant;
+use embedded_aw9523::async_traits::digital::OutputPin;
+use embedded_hal::digital::PinState;
+use getset::Getters;
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum UsbPort {
+    In,
+    Out,
+}
+
+pub struct UsbSwitch<I2C> {
+    sw: LockedOutput<I2C>,
+}
+
+impl<I2C> UsbSwitch<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c,
+{
+    pub fn new(r: UsbPins<I2C>) -> Self {
+        Self { sw: r.usb_select }
+    }
+
+    pub async fn set(&mut self, port: UsbPort) -> Result<(), PinError<I2C>> {
+        self.sw
+            .set_state(match port {
+                UsbPort::In => PinState::Low,
+                UsbPort::Out => PinState::High,
+            })
+            .await
+    }
+}
+
+/// Why the USB data lines are routed to the current port.
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum UsbRoutingReason {
+    /// Power is present on USB in, which is assumed to be a host
+    HostOnIn,
+
+    /// The badge is powering USB out, so is acting as a host for the device attached there
+    PoweringOut,
+
+    /// Nothing is attached, the data lines are left on USB in
+    NoHost,
+
+    /// Set explicitly with [`UsbManager::set_override`]
+    Override,
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq)]
+pub enum UsbChange {
+    /// Power appeared on USB in
+    InAttached,
+
+    /// Power went away from USB in
+    InDetached,
+
+    /// The badge started (`true`) or stopped powering USB out, whether anything is attached there is not known
+    OutPowered(bool),
+
+    Routed(UsbPort, UsbRoutingReason),
+}
+
+#[derive(Debug, Format, Clone, Copy, PartialEq, Eq, Getters)]
+pub struct UsbEvent {
+    #[getset(get = "pub")]
+    time: Instant,
+
+    #[getset(get = "pub")]
+    change: UsbChange,
+}
+
+/// Tracks what is attached to USB in and routes the ESP32-S3 USB to the port with a host.
+///
+/// Attachment is judged from VBUS as seen by the charger, which only sees USB in. The VBUS switch is never closed to
+/// look at USB out, as a host plugged into USB in at the same time would have its supply connected to whatever is on
+/// USB out. So nothing attached to USB out can be sensed, only whether the badge is powering it.
+pub struct UsbManager<I2C> {
+    vbus: Vbus<I2C>,
+    in_attached: bool,
+    out_powered: bool,
+    port: UsbPort,
+    reason: UsbRoutingReason,
+    port_override: Option<UsbPort>,
+}
+
+impl<I2C, E> UsbManager<I2C>
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+{
+    pub fn new(vbus: Vbus<I2C>) -> Self {
+        Self {
+            vbus,
+            in_attached: false,
+            out_powered: false,
+            port: UsbPort::In,
+            reason: UsbRoutingReason::NoHost,
+            port_override: None,
+        }
+    }
+
+    /// The port the USB data lines are currently routed to.
+    pub fn port(&self) -> UsbPort {
+        self.port
+    }
+
+    pub fn reason(&self) -> UsbRoutingReason {
+        self.reason
+    }
+
+    /// The port the data lines are routed to, if a host is expected to be there.
+    ///
+    /// This is what decides whether a USB device should be connected, see `device::run`.
+    pub fn host_port(&self) -> Option<UsbPort> {
+        match self.reason {
+            UsbRoutingReason::HostOnIn | UsbRoutingReason::Override => Some(self.port),
+            UsbRoutingReason::PoweringOut | UsbRoutingReason::NoHost => None,
+        }
+    }
+
+    /// Returns true if power is present on USB in.
+    pub fn in_attached(&self) -> bool {
+        self.in_attached
+    }
+
+    /// Returns true if the badge is powering USB out, from either the battery or USB in.
+    pub fn out_powered(&self) -> bool {
+        self.out_powered
+    }
+
+    pub fn vbus_mode(&self) -> VbusMode {
+        self.vbus.mode()
+    }
+
+    /// Access to the charger, see [`Vbus::charger`].
+    pub fn charger(&mut self) -> &mut Bq25895<Interface<I2C>> {
+        self.vbus.charger()
+    }
+
+    /// Change the power path, see [`Vbus::set_mode`].
+    ///
+    /// Takes effect on routing at the next [`UsbManager::update`].
+    pub async fn set_vbus_mode(
+        &mut self,
+        mode: VbusMode,
+    ) -> Result<(), VbusError<E, PinError<I2C>>> {
+        self.vbus.set_mode(mode).await
+    }
+
+    /// Always route the data lines to a port, or return to automatic routing with `None`.
+    ///
+    /// Takes effect at the next [`UsbManager::update`].
+    pub fn set_override(&mut self, port: Option<UsbPort>) {
+        self.port_override = port;
+    }
+
+    /// Check what is attached and update the routing, call this periodically (e.g. every second).
+    ///
+    /// This also keeps the power path in check, see [`Vbus::update`].
+    pub async fn update<M: RawMutex, const N: usize>(
+        &mut self,
+        sender: &Sender<'_, M, UsbEvent, N>,
+    ) -> Result<(), VbusError<E, PinError<I2C>>> {
+        // Also falls back from modes that no longer hold, e.g. pass through after USB in goes away
+        let in_attached = self.vbus.update().await?;
+        let out_powered = matches!(
+            self.vbus.mode(),
+            VbusMode::PowerOutFromBattery | VbusMode::PassThrough
+        );
+
+        let time = Instant::now();
+
+        if in_attached != self.in_attached {
+            self.in_attached = in_attached;
+            let change = match in_attached {
+                true => UsbChange::InAttached,
+                false => UsbChange::InDetached,
+            };
+            send(sender, time, change);
+        }
+
+        if out_powered != self.out_powered {
+            self.out_powered = out_powered;
+            send(sender, time, UsbChange::OutPowered(out_powered));
+        }
+
+        let (port, reason) = match self.port_override {
+            Some(port) => (port, UsbRoutingReason::Override),
+            None => match self.vbus.mode() {
+                VbusMode::PowerOutFromBattery => (UsbPort::Out, UsbRoutingReason::PoweringOut),
+                _ if in_attached => (UsbPort::In, UsbRoutingReason::HostOnIn),
+                _ => (UsbPort::In, UsbRoutingReason::NoHost),
+            },
+        };
+
+        // Changing power mode also sets the data switch, so always apply it
+        self.vbus
+            .usb_switch()
+            .set(port)
+            .await
+            .map_err(VbusError::Pin)?;
+
+        if (port, reason) != (self.port, self.reason) {
+            info!("USB routed to {} ({})", port, reason);
+            self.port = port;
+            self.reason = reason;
+            send(sender, time, UsbChange::Routed(port, reason));
+        }
+
+        Ok(())
+    }
+}
+
+fn send<M: RawMutex, const N: usize>(
+    sender: &Sender<'_, M, UsbEvent, N>,
+    time: Instant,
+    change: UsbChange,
+) {
+    debug!("USB: {}", change);
+    if sender.try_send(UsbEvent { time, change }).is_err() {
+        warn!("USB event channel full, dropping {}", change);
+    }
+}